@@ -1,80 +1,227 @@
-use itertools::Itertools;
-use std::str::FromStr;
+use rules::{Outcome, Rules, RulesError, Scoring};
 
-#[derive(PartialEq, Eq, Clone, Copy)]
-enum RPS {
-    Rock = 1,
-    Paper = 2,
-    Scissors = 3,
+mod rules;
+
+type Guide = Vec<(char, char)>;
+
+fn parse(input: &str) -> Option<Guide> {
+    input
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            let mut chars = l.split_whitespace().map(|t| t.chars().next());
+            Some((chars.next()??, chars.next()??))
+        })
+        .collect()
 }
-impl FromStr for RPS {
-    type Err = ();
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "A" | "X" => Ok(RPS::Rock),
-            "B" | "Y" => Ok(RPS::Paper),
-            "C" | "Z" => Ok(RPS::Scissors),
-            _ => Err(()),
-        }
-    }
+
+// One letter per shape, so at most the whole alphabet
+const MAX_SHAPES: usize = 26;
+
+// Index of `c` among the `count` letters starting at `first`
+fn letter(c: char, first: u8, count: usize) -> Option<usize> {
+    let index = u8::try_from(c).ok()?.checked_sub(first)?;
+    Some(usize::from(index)).filter(|i| *i < count)
+}
+
+// Responses use the last letters up to Z, so X Y Z for three shapes
+fn first_response(rules: &Rules) -> u8 {
+    b'Z' + 1 - rules.len() as u8
+}
+
+// Opponents use letters from A
+fn decode(rules: &Rules, guide: &Guide) -> Option<Vec<(usize, usize)>> {
+    guide
+        .iter()
+        .map(|&(a, b)| {
+            Some((
+                letter(a, b'A', rules.len())?,
+                letter(b, first_response(rules), rules.len())?,
+            ))
+        })
+        .collect()
 }
-enum Outcome {
-    Win = 6,
-    Draw = 3,
-    Lose = 0,
+
+// Same for part 2 where X Y Z say how the round has to end
+fn decode_outcomes(rules: &Rules, guide: &Guide) -> Option<Vec<(usize, Outcome)>> {
+    let outcomes = [Outcome::Lose, Outcome::Draw, Outcome::Win];
+    guide
+        .iter()
+        .map(|&(a, b)| {
+            Some((
+                letter(a, b'A', rules.len())?,
+                outcomes[letter(b, b'X', outcomes.len())?],
+            ))
+        })
+        .collect()
 }
-impl FromStr for Outcome {
-    type Err = ();
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "X" => Ok(Outcome::Lose),
-            "Y" => Ok(Outcome::Draw),
-            "Z" => Ok(Outcome::Win),
-            _ => Err(()),
+
+// Options after the mode: `rpsls`, `cycle <n>` for n shapes in a circle, and
+// `score <win> <draw> <lose>`, in any order
+fn rules(args: &[String]) -> Result<Rules, RulesError> {
+    let mut rules = Rules::rock_paper_scissors();
+    let mut scoring = None;
+    let number = |i: usize, what: &str| -> Result<u32, RulesError> {
+        let arg = args
+            .get(i)
+            .ok_or_else(|| RulesError::MissingNumber(what.to_string()))?;
+        arg.parse()
+            .map_err(|_| RulesError::InvalidNumber(arg.clone()))
+    };
+    for (i, arg) in args.iter().enumerate() {
+        match arg.as_str() {
+            "rpsls" => rules = Rules::rock_paper_scissors_lizard_spock(),
+            "cycle" => {
+                let n = number(i + 1, "number of shapes")? as usize;
+                if n > MAX_SHAPES {
+                    return Err(RulesError::TooManyShapes {
+                        shapes: n,
+                        max: MAX_SHAPES,
+                    });
+                }
+                let names: Vec<String> = (1..=n).map(|s| format!("Shape {s}")).collect();
+                rules = Rules::cycle(&names.iter().map(String::as_str).collect::<Vec<_>>())?;
+            }
+            "score" => {
+                scoring = Some(Scoring {
+                    win: number(i + 1, "win score")?,
+                    draw: number(i + 2, "draw score")?,
+                    lose: number(i + 3, "lose score")?,
+                });
+            }
+            _ => (),
         }
     }
+    // Only once the shapes are settled, so it applies whatever comes first
+    Ok(match scoring {
+        Some(scoring) => rules.with_scoring(scoring),
+        None => rules,
+    })
 }
 
-static WINS: [(RPS, RPS); 3] = [
-    (RPS::Rock, RPS::Scissors),
-    (RPS::Paper, RPS::Rock),
-    (RPS::Scissors, RPS::Paper),
-];
-fn score(game: (RPS, RPS)) -> i32 {
-    match game {
-        (a, b) if a == b => Outcome::Draw as i32 + b as i32,
-        (a, b) if WINS.contains(&(b, a)) => Outcome::Win as i32 + b as i32,
-        (_, b) => Outcome::Lose as i32 + b as i32,
-    }
+fn part1(rules: &Rules, guide: &Guide) -> u32 {
+    let guide = decode(rules, guide).expect("Invalid guide");
+    let identity: Vec<usize> = (0..rules.len()).collect();
+    rules
+        .evaluate(&guide, &identity)
+        .iter()
+        .map(|r| r.score)
+        .sum()
 }
 
-fn strategy((opponent, outcome): (RPS, Outcome)) -> (RPS, RPS) {
-    let choice = match outcome {
-        Outcome::Win => WINS.iter().find(|(_, b)| *b == opponent).unwrap().0,
-        Outcome::Lose => WINS.iter().find(|(a, _)| *a == opponent).unwrap().1,
-        Outcome::Draw => opponent,
-    };
-    (opponent, choice)
+fn part2(rules: &Rules, guide: &Guide) -> u32 {
+    decode_outcomes(rules, guide)
+        .expect("Invalid guide")
+        .into_iter()
+        .map(|(opponent, outcome)| {
+            let me = rules
+                .choose(opponent, outcome)
+                .expect("No shape for outcome");
+            rules.play(opponent, me).score
+        })
+        .sum()
 }
 
 fn main() {
     let input = std::io::read_to_string(std::io::stdin()).expect("Error reading data");
+    let guide = parse(&input).expect("Parse error");
+    let args: Vec<String> = std::env::args().collect();
+    let rules = rules(&args[1..]).unwrap_or_else(|e| panic!("Invalid rules: {e}"));
 
-    let part1: i32 = input
-        .split_whitespace()
-        .map(|t| t.parse().unwrap())
-        .tuples()
-        .map(score)
-        .sum();
-
-    let part2: i32 = input
-        .split_whitespace()
-        .tuples()
-        .map(|(opponent, outcome)| (opponent.parse().unwrap(), outcome.parse().unwrap()))
-        .map(strategy)
-        .map(score)
-        .sum();
+    match args.get(1).map(String::as_str) {
+        Some("rounds") => {
+            let decoded = decode(&rules, &guide).expect("Invalid guide");
+            let identity: Vec<usize> = (0..rules.len()).collect();
+            for round in rules.evaluate(&decoded, &identity) {
+                println!(
+                    "{} vs {}: {:?}, {} points",
+                    rules.name(round.me),
+                    rules.name(round.opponent),
+                    round.outcome,
+                    round.score
+                );
+            }
+        }
+        Some("best") => {
+            let decoded = decode(&rules, &guide).expect("Invalid guide");
+            let (mapping, total) = rules
+                .best_mapping(&decoded)
+                .unwrap_or_else(|e| panic!("No best mapping: {e}"));
+            for (symbol, shape) in mapping.iter().enumerate() {
+                println!(
+                    "{} => {}",
+                    (first_response(&rules) + symbol as u8) as char,
+                    rules.name(*shape)
+                );
+            }
+            println!("Best total: {total}");
+        }
+        _ => {
+            println!("Part 1: {}", part1(&rules, &guide));
+            println!("Part 2: {}", part2(&rules, &guide));
+        }
+    }
+}
 
-    println!("Part 1: {}", part1);
-    println!("Part 2: {}", part2);
+#[cfg(test)]
+mod test {
+    use super::*;
+    const TEST_INPUT: &str = "A Y\nB X\nC Z\n";
+    #[test]
+    fn test_part1() {
+        let guide = parse(TEST_INPUT).unwrap();
+        assert_eq!(part1(&Rules::rock_paper_scissors(), &guide), 15);
+    }
+    #[test]
+    fn test_part2() {
+        let guide = parse(TEST_INPUT).unwrap();
+        assert_eq!(part2(&Rules::rock_paper_scissors(), &guide), 12);
+    }
+    #[test]
+    fn test_lizard_spock_guide() {
+        let rules = Rules::rock_paper_scissors_lizard_spock();
+        // Spock (Z) against Scissors (C), then Lizard (Y) against Spock (E)
+        let guide = parse("C Z\nE Y\n").unwrap();
+        assert_eq!(decode(&rules, &guide), Some(vec![(2, 4), (4, 3)]));
+        assert_eq!(part1(&rules, &guide), 11 + 10);
+        assert_eq!(decode(&rules, &parse("A U\n").unwrap()), None);
+    }
+    #[test]
+    fn test_invalid_letters() {
+        let rps = Rules::rock_paper_scissors();
+        for input in ["D X", "@ X", "A W", "A \u{159}", "\u{141} X"] {
+            let guide = parse(input).unwrap();
+            assert_eq!(decode(&rps, &guide), None, "{input}");
+            assert_eq!(decode_outcomes(&rps, &guide), None, "{input}");
+        }
+        let args = |a: &str| a.split(' ').map(String::from).collect::<Vec<_>>();
+        assert_eq!(rules(&args("cycle 25")).unwrap().len(), 25);
+        assert_eq!(
+            rules(&args("cycle 27")).err(),
+            Some(RulesError::TooManyShapes {
+                shapes: 27,
+                max: 26
+            })
+        );
+        assert!(rules(&args("cycle 101")).is_err());
+        assert_eq!(
+            rules(&args("cycle")).err(),
+            Some(RulesError::MissingNumber("number of shapes".into()))
+        );
+        assert_eq!(
+            rules(&args("score 6 3")).err(),
+            Some(RulesError::MissingNumber("lose score".into()))
+        );
+        assert_eq!(
+            rules(&args("score 6 x 0")).err(),
+            Some(RulesError::InvalidNumber("x".into()))
+        );
+        // The scoring sticks no matter where it comes
+        for options in ["score 2 1 0 cycle 5", "cycle 5 score 2 1 0"] {
+            let r = rules(&args(options)).unwrap();
+            assert_eq!(r.len(), 5, "{options}");
+            assert_eq!(r.play(0, 0).score, 1 + 1, "{options}");
+            assert_eq!(r.play(0, 1).score, 2 + 2, "{options}");
+        }
+    }
 }
@@ -0,0 +1,357 @@
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Lose,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Scoring {
+    pub win: u32,
+    pub draw: u32,
+    pub lose: u32,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Scoring {
+            win: 6,
+            draw: 3,
+            lose: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shape {
+    pub name: String,
+    pub score: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RulesError {
+    UnknownShape(usize),
+    // Both shapes beat each other, or a shape beats itself
+    Contradiction(usize, usize),
+    Undecided(usize, usize),
+    TooManyShapes { shapes: usize, max: usize },
+    MissingNumber(String),
+    InvalidNumber(String),
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RulesError::UnknownShape(s) => write!(f, "there is no shape {s}"),
+            RulesError::Contradiction(a, b) => write!(f, "shapes {a} and {b} beat each other"),
+            RulesError::Undecided(a, b) => write!(f, "nothing says who wins {a} against {b}"),
+            RulesError::TooManyShapes { shapes, max } => {
+                write!(f, "{shapes} shapes, at most {max} are supported")
+            }
+            RulesError::MissingNumber(what) => write!(f, "missing {what}"),
+            RulesError::InvalidNumber(arg) => write!(f, "{arg:?} is not a valid number"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Round {
+    pub opponent: usize,
+    pub me: usize,
+    pub outcome: Outcome,
+    pub score: u32,
+}
+
+// A game where each shape has a score and `beats[a][b]` tells if a wins against b
+#[derive(Clone, Debug)]
+pub struct Rules {
+    shapes: Vec<Shape>,
+    beats: Vec<Vec<bool>>,
+    scoring: Scoring,
+}
+
+impl Rules {
+    // The mapping search goes through every subset of the shapes
+    pub const MAX_MAPPING_SHAPES: usize = 20;
+
+    // Every pair of different shapes needs exactly one winner
+    pub fn new(shapes: Vec<Shape>, wins: &[(usize, usize)]) -> Result<Self, RulesError> {
+        let n = shapes.len();
+        let mut beats = vec![vec![false; n]; n];
+        for &(a, b) in wins {
+            if let Some(s) = [a, b].into_iter().find(|s| *s >= n) {
+                return Err(RulesError::UnknownShape(s));
+            }
+            if a == b || beats[b][a] {
+                return Err(RulesError::Contradiction(a, b));
+            }
+            beats[a][b] = true;
+        }
+        for (a, row) in beats.iter().enumerate() {
+            if let Some(b) = (a + 1..n).find(|b| !row[*b] && !beats[*b][a]) {
+                return Err(RulesError::Undecided(a, b));
+            }
+        }
+        Ok(Rules {
+            shapes,
+            beats,
+            scoring: Scoring::default(),
+        })
+    }
+
+    // Shapes in a circle where each beats the half of the others right before it, which
+    // only works out for an odd number of them. Scores go up from 1 in the given order.
+    pub fn cycle(names: &[&str]) -> Result<Self, RulesError> {
+        let n = names.len();
+        let wins: Vec<(usize, usize)> = (0..n)
+            .flat_map(|a| (1..=n / 2).map(move |d| (a, (a + n - d) % n)))
+            .collect();
+        Rules::new(shapes(names), &wins)
+    }
+
+    pub fn rock_paper_scissors() -> Self {
+        Rules::cycle(&["Rock", "Paper", "Scissors"]).unwrap()
+    }
+
+    pub fn rock_paper_scissors_lizard_spock() -> Self {
+        let names = ["Rock", "Paper", "Scissors", "Lizard", "Spock"];
+        let (rock, paper, scissors, lizard, spock) = (0, 1, 2, 3, 4);
+        let wins = [
+            (scissors, paper),
+            (paper, rock),
+            (rock, lizard),
+            (lizard, spock),
+            (spock, scissors),
+            (scissors, lizard),
+            (lizard, paper),
+            (paper, spock),
+            (spock, rock),
+            (rock, scissors),
+        ];
+        Rules::new(shapes(&names), &wins).unwrap()
+    }
+
+    pub fn with_scoring(mut self, scoring: Scoring) -> Self {
+        self.scoring = scoring;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    pub fn name(&self, shape: usize) -> &str {
+        &self.shapes[shape].name
+    }
+
+    pub fn outcome(&self, opponent: usize, me: usize) -> Outcome {
+        if opponent == me {
+            Outcome::Draw
+        } else if self.beats[me][opponent] {
+            Outcome::Win
+        } else {
+            Outcome::Lose
+        }
+    }
+
+    pub fn play(&self, opponent: usize, me: usize) -> Round {
+        let outcome = self.outcome(opponent, me);
+        let points = match outcome {
+            Outcome::Win => self.scoring.win,
+            Outcome::Draw => self.scoring.draw,
+            Outcome::Lose => self.scoring.lose,
+        };
+        Round {
+            opponent,
+            me,
+            outcome,
+            score: points + self.shapes[me].score,
+        }
+    }
+
+    // Highest scoring shape that gives the outcome
+    pub fn choose(&self, opponent: usize, outcome: Outcome) -> Option<usize> {
+        (0..self.len())
+            .filter(|me| self.outcome(opponent, *me) == outcome)
+            .max_by_key(|me| self.shapes[*me].score)
+    }
+
+    // Rounds of a guide where the second column is mapped to the shape to play
+    pub fn evaluate(&self, guide: &[(usize, usize)], mapping: &[usize]) -> Vec<Round> {
+        guide
+            .iter()
+            .map(|&(opponent, symbol)| self.play(opponent, mapping[symbol]))
+            .collect()
+    }
+
+    // Mapping of the second column to shapes, one shape per symbol, that scores the most.
+    // A DP over the sets of shapes already given to the first symbols.
+    pub fn best_mapping(&self, guide: &[(usize, usize)]) -> Result<(Vec<usize>, u32), RulesError> {
+        let n = self.len();
+        if n > Rules::MAX_MAPPING_SHAPES {
+            return Err(RulesError::TooManyShapes {
+                shapes: n,
+                max: Rules::MAX_MAPPING_SHAPES,
+            });
+        }
+        let mut value = vec![vec![0; n]; n];
+        for &(opponent, symbol) in guide {
+            for (shape, v) in value[symbol].iter_mut().enumerate() {
+                *v += self.play(opponent, shape).score;
+            }
+        }
+        let mut best: Vec<Option<(u32, usize)>> = vec![None; 1 << n];
+        best[0] = Some((0, 0));
+        for used in 0..1usize << n {
+            let Some((score, _)) = best[used] else {
+                continue;
+            };
+            let symbol = used.count_ones() as usize;
+            if symbol == n {
+                continue;
+            }
+            for shape in (0..n).filter(|s| used & 1 << s == 0) {
+                let next = used | 1 << shape;
+                let candidate = score + value[symbol][shape];
+                if best[next].is_none_or(|(s, _)| candidate > s) {
+                    best[next] = Some((candidate, shape));
+                }
+            }
+        }
+        let (total, _) = best[(1 << n) - 1].unwrap();
+        let mut mapping = vec![0; n];
+        let mut used = (1 << n) - 1;
+        for symbol in (0..n).rev() {
+            let shape = best[used].unwrap().1;
+            mapping[symbol] = shape;
+            used &= !(1 << shape);
+        }
+        Ok((mapping, total))
+    }
+}
+
+fn shapes(names: &[&str]) -> Vec<Shape> {
+    names
+        .iter()
+        .zip(1..)
+        .map(|(name, score)| Shape {
+            name: name.to_string(),
+            score,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const GUIDE: [(usize, usize); 3] = [(0, 1), (1, 0), (2, 2)];
+
+    #[test]
+    fn test_rock_paper_scissors() {
+        let rules = Rules::rock_paper_scissors();
+        let rounds = rules.evaluate(&GUIDE, &[0, 1, 2]);
+        assert_eq!(
+            rounds
+                .iter()
+                .map(|r| (r.outcome, r.score))
+                .collect::<Vec<_>>(),
+            [(Outcome::Win, 8), (Outcome::Lose, 1), (Outcome::Draw, 6)]
+        );
+        assert_eq!(rules.choose(0, Outcome::Lose), Some(2));
+        let (mapping, total) = rules.best_mapping(&GUIDE).unwrap();
+        assert_eq!(total, 9 + 8 + 7);
+        assert_eq!(mapping, [2, 1, 0]);
+    }
+    #[test]
+    fn test_lizard_spock() {
+        let rules = Rules::rock_paper_scissors_lizard_spock();
+        let (rock, paper, scissors, lizard, spock) = (0, 1, 2, 3, 4);
+        for shape in 0..5 {
+            let wins = (0..5).filter(|o| rules.outcome(*o, shape) == Outcome::Win);
+            assert_eq!(wins.count(), 2, "{}", rules.name(shape));
+        }
+        assert_eq!(rules.outcome(spock, lizard), Outcome::Win);
+        assert_eq!(rules.outcome(rock, paper), Outcome::Win);
+        assert_eq!(rules.outcome(scissors, spock), Outcome::Win);
+        assert_eq!(rules.choose(rock, Outcome::Win), Some(spock));
+        // A 5-cycle in the right order is the same game
+        let cycle = Rules::cycle(&["Rock", "Spock", "Paper", "Lizard", "Scissors"]).unwrap();
+        let order = [rock, spock, paper, lizard, scissors];
+        for a in 0..5 {
+            for b in 0..5 {
+                assert_eq!(cycle.outcome(a, b), rules.outcome(order[a], order[b]));
+            }
+        }
+    }
+    #[test]
+    fn test_best_mapping() {
+        // Checked against all mappings of a bigger game
+        let rules = Rules::cycle(&["a", "b", "c", "d", "e", "f", "g"])
+            .unwrap()
+            .with_scoring(Scoring {
+                win: 10,
+                draw: 4,
+                lose: 1,
+            });
+        let guide: Vec<(usize, usize)> = (0..60).map(|i| (i * 5 % 7, i * i % 7)).collect();
+        let (mapping, total) = rules.best_mapping(&guide).unwrap();
+        let score = |m: &[usize]| {
+            rules
+                .evaluate(&guide, m)
+                .iter()
+                .map(|r| r.score)
+                .sum::<u32>()
+        };
+        assert_eq!(score(&mapping), total);
+        let mut best = 0;
+        let mut m: Vec<usize> = (0..7).collect();
+        // Heap's algorithm
+        let mut c = [0; 7];
+        best = best.max(score(&m));
+        let mut i = 0;
+        while i < 7 {
+            if c[i] < i {
+                m.swap(if i % 2 == 0 { 0 } else { c[i] }, i);
+                best = best.max(score(&m));
+                c[i] += 1;
+                i = 0;
+            } else {
+                c[i] = 0;
+                i += 1;
+            }
+        }
+        assert_eq!(total, best);
+    }
+    #[test]
+    fn test_errors() {
+        let names = shapes(&["a", "b", "c"]);
+        assert_eq!(
+            Rules::new(names.clone(), &[(0, 1), (1, 2)]).err(),
+            Some(RulesError::Undecided(0, 2))
+        );
+        assert_eq!(
+            Rules::new(names.clone(), &[(0, 1), (1, 0)]).err(),
+            Some(RulesError::Contradiction(1, 0))
+        );
+        assert_eq!(
+            Rules::new(names, &[(0, 3)]).err(),
+            Some(RulesError::UnknownShape(3))
+        );
+        assert_eq!(
+            Rules::cycle(&["a", "b", "c", "d"]).err(),
+            Some(RulesError::Contradiction(2, 0))
+        );
+        let names: Vec<String> = (0..21).map(|i| i.to_string()).collect();
+        let big = Rules::cycle(&names.iter().map(String::as_str).collect::<Vec<_>>()).unwrap();
+        assert_eq!(
+            big.best_mapping(&[]).err(),
+            Some(RulesError::TooManyShapes {
+                shapes: 21,
+                max: 20
+            })
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt;
+use std::slice::ChunksExact;
+
+pub fn priority(item: char) -> Option<u32> {
+    match item {
+        'a'..='z' => Some(u32::from(item) - u32::from('a') + 1),
+        'A'..='Z' => Some(u32::from(item) - u32::from('A') + 27),
+        _ => None,
+    }
+}
+
+pub fn item(priority: u32) -> Option<char> {
+    match priority {
+        1..=26 => Some(char::from(b'a' - 1 + priority as u8)),
+        27..=52 => Some(char::from(b'A' - 27 + priority as u8)),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RucksackError {
+    InvalidItem(char),
+    EmptyGroup,
+}
+
+impl fmt::Display for RucksackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RucksackError::InvalidItem(ch) => write!(f, "{ch:?} is not an item"),
+            RucksackError::EmptyGroup => write!(f, "groups need at least one rucksack"),
+        }
+    }
+}
+
+// Set of items with bit n standing for priority n
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Items(u64);
+
+impl Items {
+    const ALL: Items = Items(((1 << 52) - 1) << 1);
+
+    pub fn new(items: &str) -> Result<Self, RucksackError> {
+        items.chars().try_fold(Items(0), |set, ch| {
+            let p = priority(ch).ok_or(RucksackError::InvalidItem(ch))?;
+            Ok(Items(set.0 | 1 << p))
+        })
+    }
+
+    pub fn contains(&self, priority: u32) -> bool {
+        self.0 & 1 << priority != 0
+    }
+
+    pub fn intersection(self, other: Items) -> Items {
+        Items(self.0 & other.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    // Priorities in the set, lowest first
+    pub fn priorities(self) -> impl Iterator<Item = u32> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            (bits != 0).then(|| {
+                let p = bits.trailing_zeros();
+                bits &= bits - 1;
+                p
+            })
+        })
+    }
+}
+
+// Items found in every member of the group, with how many times each shows up in total
+pub fn common(group: &[&str]) -> Result<Vec<(char, usize)>, RucksackError> {
+    let mut shared = Items::ALL;
+    for member in group {
+        shared = shared.intersection(Items::new(member)?);
+    }
+    if group.is_empty() {
+        shared = Items::default();
+    }
+    let mut counts = [0; 53];
+    for ch in group.iter().flat_map(|m| m.chars()) {
+        let p = priority(ch).unwrap();
+        if shared.contains(p) {
+            counts[p as usize] += 1;
+        }
+    }
+    Ok(shared
+        .priorities()
+        .map(|p| (item(p).unwrap(), counts[p as usize]))
+        .collect())
+}
+
+pub fn compartments(rucksack: &str) -> [&str; 2] {
+    let (a, b) = rucksack.split_at(rucksack.len() / 2);
+    [a, b]
+}
+
+// Priority sum of what both compartments of each rucksack have in common
+pub fn part1(rucksacks: &[&str]) -> Result<u32, RucksackError> {
+    rucksacks.iter().try_fold(0, |sum, r| {
+        let shared = common(&compartments(r))?;
+        Ok(sum
+            + shared
+                .iter()
+                .map(|(ch, _)| priority(*ch).unwrap())
+                .sum::<u32>())
+    })
+}
+
+// Consecutive groups of `size` rucksacks, a partial group at the end is left out
+pub fn groups<'a>(
+    rucksacks: &'a [&'a str],
+    size: usize,
+) -> Result<ChunksExact<'a, &'a str>, RucksackError> {
+    if size == 0 {
+        return Err(RucksackError::EmptyGroup);
+    }
+    Ok(rucksacks.chunks_exact(size))
+}
+
+// Same as part 1 for groups of any number of rucksacks
+pub fn part2(rucksacks: &[&str], group_size: usize) -> Result<u32, RucksackError> {
+    groups(rucksacks, group_size)?.try_fold(0, |sum, group| {
+        let shared = common(group)?;
+        Ok(sum
+            + shared
+                .iter()
+                .map(|(ch, _)| priority(*ch).unwrap())
+                .sum::<u32>())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_INPUT: [&str; 6] = [
+        "vJrwpWtwJgWrhcsFMMfFFhFp",
+        "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
+        "PmmdzqPrVvPwwTWBwg",
+        "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn",
+        "ttgJtRGJQctTZtZT",
+        "CrZsJsPPZsGzwwsLwLmpwMDw",
+    ];
+
+    #[test]
+    fn test_parts() {
+        assert_eq!(part1(&TEST_INPUT), Ok(157));
+        assert_eq!(part2(&TEST_INPUT, 3), Ok(70));
+        assert_eq!(part2(&TEST_INPUT, 6), Ok(0));
+        assert_eq!(part2(&TEST_INPUT[..5], 3), Ok(18));
+        assert_eq!(part2(&TEST_INPUT, 0), Err(RucksackError::EmptyGroup));
+    }
+    #[test]
+    fn test_common() {
+        assert_eq!(common(&compartments(TEST_INPUT[0])), Ok(vec![('p', 2)]));
+        assert_eq!(common(&TEST_INPUT[0..3]), Ok(vec![('r', 6)]));
+        assert_eq!(
+            common(&["abcA", "xAcab", "Acc"]),
+            Ok(vec![('c', 4), ('A', 3)])
+        );
+        assert_eq!(common(&["abc"]), Ok(vec![('a', 1), ('b', 1), ('c', 1)]));
+        assert_eq!(common(&[]), Ok(vec![]));
+        assert_eq!(common(&["ab", "a1"]), Err(RucksackError::InvalidItem('1')));
+    }
+    #[test]
+    fn test_items() {
+        for p in 1..=52 {
+            assert_eq!(item(p).and_then(priority), Some(p));
+        }
+        assert_eq!(item(0), None);
+        assert_eq!(item(53), None);
+        assert_eq!(item(300), None);
+        let items = Items::new("zaZAaz").unwrap();
+        assert_eq!(items.len(), 4);
+        assert_eq!(items.priorities().collect::<Vec<_>>(), [1, 26, 27, 52]);
+        assert!(Items::new("").unwrap().is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use day3::bitset;
 use itertools::Itertools;
 use std::collections::HashSet;
 
@@ -9,6 +10,26 @@ fn main() {
         .map(|l| l.expect("Error reading line"))
         .filter(|l| !l.is_empty())
         .collect();
+    // Everything each group has in common, with how often it shows up in the group
+    if std::env::args().nth(1).as_deref() == Some("groups") {
+        let size = std::env::args()
+            .nth(2)
+            .map_or(3, |n| n.parse().expect("Invalid group size"));
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let groups = match bitset::groups(&lines, size) {
+            Ok(groups) => groups,
+            Err(e) => {
+                eprintln!("Invalid group size: {e}");
+                std::process::exit(1);
+            }
+        };
+        for (i, group) in groups.enumerate() {
+            let common = bitset::common(group).expect("Invalid item");
+            let items: Vec<String> = common.iter().map(|(ch, n)| format!("{ch} ({n})")).collect();
+            println!("Group {}: {}", i + 1, items.join(", "));
+        }
+        return;
+    }
     let part1: usize = lines
         .iter()
         .map(|l| l.split_at(l.len() / 2))
@@ -0,0 +1,102 @@
+// Runs both binaries on large random inputs and checks they agree with the bitset version
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use day3::bitset::{self, item};
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, xs: &mut [T]) {
+        for i in (1..xs.len()).rev() {
+            xs.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+// Groups of three rucksacks with exactly one badge per group and exactly one item
+// shared by the compartments of each rucksack, like the puzzle promises
+fn generate(rng: &mut XorShift, groups: usize) -> String {
+    let mut out = String::new();
+    for _ in 0..groups {
+        let badge = rng.below(52) as u32 + 1;
+        let mut allowed = [[true; 53]; 3];
+        for p in (1..=52).filter(|p| *p != badge) {
+            allowed[rng.below(3)][p as usize] = false;
+        }
+        for allowed in allowed {
+            let candidates: Vec<u32> = (1..=52).filter(|p| allowed[*p as usize]).collect();
+            let shared = candidates[rng.below(candidates.len())];
+            let (mut left, mut right) = (vec![shared], vec![shared]);
+            if badge != shared {
+                [&mut left, &mut right][rng.below(2)].push(badge);
+            }
+            for p in candidates
+                .into_iter()
+                .filter(|p| *p != shared && *p != badge)
+            {
+                match rng.below(3) {
+                    0 => left.push(p),
+                    1 => right.push(p),
+                    _ => (),
+                }
+            }
+            while left.len() < right.len() {
+                left.push(left[rng.below(left.len())]);
+            }
+            while right.len() < left.len() {
+                right.push(right[rng.below(right.len())]);
+            }
+            rng.shuffle(&mut left);
+            rng.shuffle(&mut right);
+            out.extend(left.into_iter().chain(right).map(|p| item(p).unwrap()));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn run(exe: &str, input: &str) -> (u32, u32) {
+    let mut child = Command::new(exe)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Can't start binary");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = String::from_utf8(child.wait_with_output().unwrap().stdout).unwrap();
+    let part = |prefix: &str| {
+        output
+            .lines()
+            .find_map(|l| l.strip_prefix(prefix))
+            .and_then(|n| n.parse().ok())
+            .expect("Missing answer")
+    };
+    (part("Part 1: "), part("Part 2: "))
+}
+
+#[test]
+fn test_agree() {
+    let mut rng = XorShift(0x2022_0003);
+    for _ in 0..3 {
+        let input = generate(&mut rng, 2000);
+        let lines: Vec<&str> = input.lines().collect();
+        let expected = (
+            bitset::part1(&lines).unwrap(),
+            bitset::part2(&lines, 3).unwrap(),
+        );
+        assert_eq!(run(env!("CARGO_BIN_EXE_day3"), &input), expected);
+        assert_eq!(run(env!("CARGO_BIN_EXE_day3_opt"), &input), expected);
+    }
+}
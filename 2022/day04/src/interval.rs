@@ -0,0 +1,184 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+// Closed range of sections, both ends included
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Interval {
+    pub start: i32,
+    pub end: i32,
+}
+
+// Allen's interval relations, read as "a <relation> b". Sections are whole numbers,
+// so ranges meet when one ends right before the other starts without sharing a section.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Relation {
+    Before,
+    Meets,
+    Overlaps,
+    Starts,
+    During,
+    Finishes,
+    Equals,
+    FinishedBy,
+    Contains,
+    StartedBy,
+    OverlappedBy,
+    MetBy,
+    After,
+}
+
+impl Relation {
+    pub const ALL: [Relation; 13] = [
+        Relation::Before,
+        Relation::Meets,
+        Relation::Overlaps,
+        Relation::Starts,
+        Relation::During,
+        Relation::Finishes,
+        Relation::Equals,
+        Relation::FinishedBy,
+        Relation::Contains,
+        Relation::StartedBy,
+        Relation::OverlappedBy,
+        Relation::MetBy,
+        Relation::After,
+    ];
+
+    // The relation of b to a
+    pub fn inverse(self) -> Relation {
+        Relation::ALL[Relation::ALL.len() - 1 - self as usize]
+    }
+
+    // One of the ranges has all sections of the other
+    pub fn is_containment(self) -> bool {
+        use Relation::*;
+        matches!(
+            self,
+            Starts | During | Finishes | Equals | FinishedBy | Contains | StartedBy
+        )
+    }
+
+    // The ranges have at least one section in common
+    pub fn is_overlap(self) -> bool {
+        use Relation::*;
+        !matches!(self, Before | Meets | MetBy | After)
+    }
+}
+
+impl fmt::Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Relation::Before => "before",
+            Relation::Meets => "meets",
+            Relation::Overlaps => "overlaps",
+            Relation::Starts => "starts",
+            Relation::During => "during",
+            Relation::Finishes => "finishes",
+            Relation::Equals => "equals",
+            Relation::FinishedBy => "finished by",
+            Relation::Contains => "contains",
+            Relation::StartedBy => "started by",
+            Relation::OverlappedBy => "overlapped by",
+            Relation::MetBy => "met by",
+            Relation::After => "after",
+        };
+        f.pad(name)
+    }
+}
+
+impl Interval {
+    // None when the range is reversed
+    pub fn new(start: i32, end: i32) -> Option<Self> {
+        (start <= end).then_some(Interval { start, end })
+    }
+
+    pub fn relation(&self, other: &Interval) -> Relation {
+        // Half open ends make the comparisons the same as for continuous intervals
+        let (a0, a1) = (self.start as i64, self.end as i64 + 1);
+        let (b0, b1) = (other.start as i64, other.end as i64 + 1);
+        use Ordering::*;
+        use Relation::*;
+        if a1 < b0 {
+            Before
+        } else if a1 == b0 {
+            Meets
+        } else if b1 < a0 {
+            After
+        } else if b1 == a0 {
+            MetBy
+        } else {
+            match (a0.cmp(&b0), a1.cmp(&b1)) {
+                (Equal, Equal) => Equals,
+                (Equal, Less) => Starts,
+                (Equal, Greater) => StartedBy,
+                (Greater, Equal) => Finishes,
+                (Less, Equal) => FinishedBy,
+                (Greater, Less) => During,
+                (Less, Greater) => Contains,
+                (Less, Less) => Overlaps,
+                (Greater, Greater) => OverlappedBy,
+            }
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn relation(a: (i32, i32), b: (i32, i32)) -> Relation {
+        Interval::new(a.0, a.1)
+            .unwrap()
+            .relation(&Interval::new(b.0, b.1).unwrap())
+    }
+
+    #[test]
+    fn test_relations() {
+        use Relation::*;
+        let cases = [
+            ((2, 4), (6, 8), Before),
+            ((2, 4), (5, 8), Meets),
+            ((2, 6), (4, 8), Overlaps),
+            ((2, 4), (2, 8), Starts),
+            ((3, 7), (2, 8), During),
+            ((6, 8), (2, 8), Finishes),
+            ((2, 8), (2, 8), Equals),
+            ((6, 6), (4, 6), Finishes),
+            ((4, 4), (4, 4), Equals),
+            ((5, 5), (6, 6), Meets),
+        ];
+        for (a, b, r) in cases {
+            assert_eq!(relation(a, b), r, "{a:?} {b:?}");
+            assert_eq!(relation(b, a), r.inverse(), "{b:?} {a:?}");
+        }
+    }
+    #[test]
+    fn test_properties() {
+        // Small ranges against a fixed one cover every relation
+        let fixed = Interval::new(3, 5).unwrap();
+        let mut seen = Vec::new();
+        for start in 0..9 {
+            for end in start..9 {
+                let a = Interval::new(start, end).unwrap();
+                let r = a.relation(&fixed);
+                assert_eq!(fixed.relation(&a), r.inverse());
+                let contained = (a.start <= fixed.start && a.end >= fixed.end)
+                    || (fixed.start <= a.start && fixed.end >= a.end);
+                assert_eq!(r.is_containment(), contained);
+                assert_eq!(r.is_overlap(), a.start <= fixed.end && fixed.start <= a.end);
+                if !seen.contains(&r) {
+                    seen.push(r);
+                }
+            }
+        }
+        assert_eq!(seen.len(), Relation::ALL.len());
+        assert!(Relation::ALL.iter().all(|r| r.inverse().inverse() == *r));
+        assert_eq!(Interval::new(7, 3), None);
+    }
+}
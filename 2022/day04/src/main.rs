@@ -1,43 +1,91 @@
-use nom::{character::complete as ncc, sequence as ns};
-
-fn range_parser(i: &str) -> nom::IResult<&str, (i32, i32)> {
-    ns::separated_pair(ncc::i32, ncc::char('-'), ncc::i32)(i)
-}
-
-fn part1(input: &str) -> i32 {
-    let contains = |(a0, b0): (i32, i32), (a1, b1): (i32, i32)| a0 <= a1 && b0 >= b1;
-    nom::multi::fold_many0(
-        ns::terminated(
-            ns::separated_pair(range_parser, ncc::char(','), range_parser),
-            ncc::multispace0,
-        ),
-        || 0,
-        move |result, (r1, r2)| {
-            (contains(r1, r2) || contains(r2, r1))
-                .then_some(result + 1)
-                .unwrap_or(result)
-        },
-    )(input)
-    .unwrap()
-    .1
-}
-
-fn part2(input: &str) -> i32 {
-    let overlaps = |(a0, b0): (i32, i32), (a1, b1): (i32, i32)| a0 <= b1 && a1 <= b0;
-    nom::multi::fold_many0(
-        ns::terminated(
-            ns::separated_pair(range_parser, ncc::char(','), range_parser),
-            ncc::multispace0,
-        ),
-        || 0,
-        move |result, (r1, r2)| overlaps(r1, r2).then_some(result + 1).unwrap_or(result),
-    )(input)
-    .unwrap()
-    .1
+use interval::{Interval, Relation};
+use nom::{character::complete as ncc, combinator as nc, sequence as ns, Finish};
+
+mod interval;
+
+type Pairs = Vec<(Interval, Interval)>;
+
+// Reversed ranges like 7-3 are rejected
+fn range_parser(i: &str) -> nom::IResult<&str, Interval> {
+    nc::map_opt(
+        ns::separated_pair(ncc::i32, ncc::char('-'), ncc::i32),
+        |(start, end)| Interval::new(start, end),
+    )(i)
+}
+
+fn parse(input: &str) -> nom::IResult<&str, Pairs> {
+    nc::all_consuming(nom::multi::many0(ns::terminated(
+        ns::separated_pair(range_parser, ncc::char(','), range_parser),
+        ncc::multispace0,
+    )))(input)
+}
+
+fn count(pairs: &Pairs, f: impl Fn(Relation) -> bool) -> usize {
+    pairs.iter().filter(|(a, b)| f(a.relation(b))).count()
+}
+
+fn part1(pairs: &Pairs) -> usize {
+    count(pairs, Relation::is_containment)
+}
+
+fn part2(pairs: &Pairs) -> usize {
+    count(pairs, Relation::is_overlap)
+}
+
+// Relation of every pair, followed by how often each one came up
+fn report(pairs: &Pairs) -> String {
+    let mut out = String::new();
+    for (a, b) in pairs {
+        out.push_str(&format!("{a},{b}: {}\n", a.relation(b)));
+    }
+    // The order within a pair is arbitrary, so also count the relations either way round
+    for relation in Relation::ALL {
+        let n = count(pairs, |r| r == relation);
+        let either = count(pairs, |r| r == relation || r == relation.inverse());
+        out.push_str(&format!("{relation:>13}: {n} ({either} either way)\n"));
+    }
+    out
 }
 
 fn main() {
     let input = std::io::read_to_string(std::io::stdin()).unwrap();
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    let (_, pairs) = parse(&input).finish().expect("Parse error");
+    if std::env::args().nth(1).as_deref() == Some("report") {
+        print!("{}", report(&pairs));
+    }
+    println!("Part 1: {}", part1(&pairs));
+    println!("Part 2: {}", part2(&pairs));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    const TEST_INPUT: &str = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8\n";
+    #[test]
+    fn test_part1() {
+        let (_, pairs) = parse(TEST_INPUT).finish().expect("Parse error");
+        assert_eq!(part1(&pairs), 2);
+    }
+    #[test]
+    fn test_part2() {
+        let (_, pairs) = parse(TEST_INPUT).finish().expect("Parse error");
+        assert_eq!(part2(&pairs), 4);
+    }
+    #[test]
+    fn test_report() {
+        let (_, pairs) = parse(TEST_INPUT).finish().expect("Parse error");
+        let report = report(&pairs);
+        assert!(report.starts_with("2-4,6-8: before\n2-3,4-5: meets\n5-7,7-9: overlaps\n"));
+        assert!(report.contains("\n     contains: 1 (1 either way)\n"));
+        assert!(report.contains("\n     overlaps: 2 (2 either way)\n"));
+        assert!(report.contains("\n     finishes: 1 (1 either way)\n"));
+        assert!(report.ends_with("\n        after: 0 (1 either way)\n"));
+    }
+    #[test]
+    fn test_invalid_input() {
+        assert!(parse("2-4,6-8\n2-3,4-x\n5-7,7-9\n").finish().is_err());
+        assert!(parse("2-4,6-8\n7-3,1-2\n").finish().is_err());
+        assert!(parse("2-4,6-8 junk").finish().is_err());
+        assert_eq!(parse("").finish(), Ok(("", vec![])));
+    }
 }
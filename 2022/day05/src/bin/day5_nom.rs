@@ -1,3 +1,4 @@
+use day5::crane::{self, Action, CrateMover9000, CrateMover9001, Stacks};
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -7,8 +8,6 @@ use nom::{
     sequence::{delimited, preceded, terminated, tuple},
     IResult,
 };
-type Stacks = Vec<Vec<char>>;
-type Action = (usize, usize, usize);
 
 fn parse_stacks(input: &str) -> IResult<&str, Stacks> {
     let filled_slot = map(
@@ -52,33 +51,18 @@ fn parse_actions(input: &str) -> IResult<&str, Vec<Action>> {
     many1(parse_action)(input)
 }
 
-fn part1(stacks: &mut Stacks, (n, from, to): Action) {
-    for _ in 0..n {
-        let ch = stacks[from - 1].pop().unwrap();
-        stacks[to - 1].push(ch);
-    }
-}
-
-fn part2(stacks: &mut Stacks, (n, from, to): Action) {
-    let crates: Vec<char> = (0..n)
-        .map(|_| stacks[from - 1].pop().unwrap())
-        .rev()
-        .collect();
-    stacks[to - 1].extend(crates.into_iter().rev());
-}
-
-fn execute(mut stacks: Stacks, actions: &Vec<Action>, command: fn(&mut Stacks, Action)) -> String {
-    actions.iter().for_each(|i| command(&mut stacks, *i));
-    let result = stacks.iter().map(|s| s.last()).collect::<Option<String>>();
-    result.unwrap()
-}
-
 fn main() {
     let input = std::io::read_to_string(std::io::stdin()).unwrap();
     let (stacks_input, actions_input) = input.split_once("\n\n").unwrap();
-    let (_, stacks) = parse_stacks(&stacks_input).expect("Error parsing stacks");
+    let (_, stacks) = parse_stacks(stacks_input).expect("Error parsing stacks");
     let (_, actions) = parse_actions(actions_input).expect("Error parsing actions");
 
-    println!("Part 1: {}", execute(stacks.clone(), &actions, part1));
-    println!("Part 2: {}", execute(stacks, &actions, part2));
+    println!(
+        "Part 1: {}",
+        crane::execute(stacks.clone(), &actions, &CrateMover9000).expect("Invalid move")
+    );
+    println!(
+        "Part 2: {}",
+        crane::execute(stacks, &actions, &CrateMover9001).expect("Invalid move")
+    );
 }
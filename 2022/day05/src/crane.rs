@@ -0,0 +1,254 @@
+use std::fmt;
+
+pub type Stacks = Vec<Vec<char>>;
+// Crate count and the source and target stack numbers, starting from 1 like in the input
+pub type Action = (usize, usize, usize);
+
+pub trait Crane {
+    // Moves the top `count` crates of `from` onto `to`, the move has already been checked
+    fn lift(&self, from: &mut Vec<char>, to: &mut Vec<char>, count: usize);
+}
+
+// Moves crates one at a time, reversing their order
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn lift(&self, from: &mut Vec<char>, to: &mut Vec<char>, count: usize) {
+        for _ in 0..count {
+            to.push(from.pop().unwrap());
+        }
+    }
+}
+
+// Moves all crates at once, keeping their order
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn lift(&self, from: &mut Vec<char>, to: &mut Vec<char>, count: usize) {
+        to.extend(from.drain(from.len() - count..));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    NoSuchStack(usize),
+    Underflow {
+        stack: usize,
+        requested: usize,
+        available: usize,
+    },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NoSuchStack(stack) => write!(f, "There is no stack {stack}"),
+            MoveError::Underflow {
+                stack,
+                requested,
+                available,
+            } => write!(
+                f,
+                "Can't move {requested} crates from stack {stack} holding {available}"
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Yard {
+    stacks: Stacks,
+}
+
+impl Yard {
+    pub fn new(stacks: Stacks) -> Self {
+        Yard { stacks }
+    }
+
+    pub fn stacks(&self) -> &Stacks {
+        &self.stacks
+    }
+
+    // Leaves the stacks untouched if the move isn't possible
+    pub fn apply(&mut self, crane: &dyn Crane, (count, from, to): Action) -> Result<(), MoveError> {
+        for stack in [from, to] {
+            if stack == 0 || stack > self.stacks.len() {
+                return Err(MoveError::NoSuchStack(stack));
+            }
+        }
+        let available = self.stacks[from - 1].len();
+        if count > available {
+            return Err(MoveError::Underflow {
+                stack: from,
+                requested: count,
+                available,
+            });
+        }
+        // The crane still does the work, putting the crates back where they came from
+        if from == to {
+            let stack = &mut self.stacks[from - 1];
+            let mut held = Vec::with_capacity(count);
+            crane.lift(stack, &mut held, count);
+            crane.lift(&mut held, stack, count);
+            return Ok(());
+        }
+        let (source, target) = if from < to {
+            let (left, right) = self.stacks.split_at_mut(to - 1);
+            (&mut left[from - 1], &mut right[0])
+        } else {
+            let (left, right) = self.stacks.split_at_mut(from - 1);
+            (&mut right[0], &mut left[to - 1])
+        };
+        crane.lift(source, target, count);
+        Ok(())
+    }
+
+    // Top crate of each stack, with a space for empty stacks
+    pub fn tops(&self) -> String {
+        self.stacks
+            .iter()
+            .map(|s| s.last().copied().unwrap_or(' '))
+            .collect()
+    }
+}
+
+// Same drawing as in the puzzle input, including the stack numbers
+impl fmt::Display for Yard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self.stacks.iter().map(|s| s.len()).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            let row: Vec<String> = self
+                .stacks
+                .iter()
+                .map(|s| match s.get(level) {
+                    Some(c) => format!("[{c}]"),
+                    None => "   ".into(),
+                })
+                .collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        let numbers: Vec<String> = (1..=self.stacks.len()).map(|n| format!("{n:^3}")).collect();
+        write!(f, "{}", numbers.join(" "))
+    }
+}
+
+pub fn execute(stacks: Stacks, actions: &[Action], crane: &dyn Crane) -> Result<String, MoveError> {
+    let mut yard = Yard::new(stacks);
+    for action in actions {
+        yard.apply(crane, *action)?;
+    }
+    Ok(yard.tops())
+}
+
+// The yard before and after each action, as shown with `animate`
+pub fn animate(stacks: Stacks, actions: &[Action], crane: &dyn Crane) -> Result<String, MoveError> {
+    let mut yard = Yard::new(stacks);
+    let mut out = format!("{yard}\n\n");
+    for &(count, from, to) in actions {
+        yard.apply(crane, (count, from, to))?;
+        out += &format!("move {count} from {from} to {to}\n{yard}\n\n");
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn yard() -> Yard {
+        Yard::new(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']])
+    }
+
+    #[test]
+    fn test_models() {
+        let mut a = yard();
+        a.apply(&CrateMover9000, (2, 2, 1)).unwrap();
+        assert_eq!(a.stacks()[0], ['Z', 'N', 'D', 'C']);
+        let mut b = yard();
+        b.apply(&CrateMover9001, (2, 2, 1)).unwrap();
+        assert_eq!(b.stacks()[0], ['Z', 'N', 'C', 'D']);
+        b.apply(&CrateMover9001, (4, 1, 3)).unwrap();
+        assert_eq!(b.tops(), " MD");
+    }
+    #[test]
+    fn test_custom_crane() {
+        // Lifts two crates at a time
+        struct Pairs;
+        impl Crane for Pairs {
+            fn lift(&self, from: &mut Vec<char>, to: &mut Vec<char>, count: usize) {
+                for chunk in (0..count).step_by(2) {
+                    let n = (count - chunk).min(2);
+                    CrateMover9001.lift(from, to, n);
+                }
+            }
+        }
+        let mut y = yard();
+        y.apply(&Pairs, (3, 2, 3)).unwrap();
+        assert_eq!(y.stacks()[2], ['P', 'C', 'D', 'M']);
+    }
+    #[test]
+    fn test_invalid_moves() {
+        let mut y = yard();
+        assert_eq!(
+            y.apply(&CrateMover9000, (3, 1, 2)),
+            Err(MoveError::Underflow {
+                stack: 1,
+                requested: 3,
+                available: 2
+            })
+        );
+        assert_eq!(
+            y.apply(&CrateMover9000, (1, 1, 4)),
+            Err(MoveError::NoSuchStack(4))
+        );
+        assert_eq!(
+            y.apply(&CrateMover9000, (1, 0, 1)),
+            Err(MoveError::NoSuchStack(0))
+        );
+        assert_eq!(y, yard());
+        y.apply(&CrateMover9000, (2, 1, 1)).unwrap();
+        assert_eq!(y, yard());
+        assert_eq!(
+            y.apply(&CrateMover9001, (3, 1, 1)),
+            Err(MoveError::Underflow {
+                stack: 1,
+                requested: 3,
+                available: 2
+            })
+        );
+    }
+    #[test]
+    fn test_self_move() {
+        // Counts what the crane is asked to lift
+        struct Counting(std::cell::Cell<usize>);
+        impl Crane for Counting {
+            fn lift(&self, from: &mut Vec<char>, to: &mut Vec<char>, count: usize) {
+                self.0.set(self.0.get() + count);
+                CrateMover9000.lift(from, to, count);
+            }
+        }
+        let crane = Counting(Default::default());
+        let mut y = yard();
+        y.apply(&crane, (3, 2, 2)).unwrap();
+        assert_eq!(crane.0.get(), 6);
+        assert_eq!(y, yard());
+    }
+    #[test]
+    fn test_animate() {
+        let stacks = yard().stacks().clone();
+        let frames = |crane: &dyn Crane| animate(stacks.clone(), &[(2, 2, 1)], crane).unwrap();
+        assert!(frames(&CrateMover9000).ends_with("move 2 from 2 to 1\n[C]        \n[D]        \n[N]        \n[Z] [M] [P]\n 1   2   3 \n\n"));
+        assert!(frames(&CrateMover9001).contains("[D]        \n[C]        \n[N]"));
+        assert_eq!(
+            animate(stacks, &[(1, 4, 1)], &CrateMover9000),
+            Err(MoveError::NoSuchStack(4))
+        );
+    }
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            yard().to_string(),
+            "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 "
+        );
+    }
+}
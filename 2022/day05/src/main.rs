@@ -1,6 +1,5 @@
+use day5::crane::{self, Action, Crane, CrateMover9000, CrateMover9001, Stacks};
 use itertools::Itertools;
-type Stacks = Vec<Vec<char>>;
-type Action = (usize, usize, usize);
 
 fn parse_stacks(input: &str) -> Stacks {
     let lines: Vec<_> = input.lines().rev().collect();
@@ -26,34 +25,69 @@ fn parse_actions(input: &str) -> Vec<Action> {
         .filter(|l| !l.is_empty())
         .map(|l| l.split(' ').skip(1).step_by(2).map(|x| x.parse().unwrap()))
         .map(|mut xs| xs.next_tuple().unwrap())
-        .map(|(n, from, to)| (n, from - 1, to - 1))
         .collect()
 }
 
-fn part1(stacks: &mut Stacks, (n, from, to): Action) {
-    for _ in 0..n {
-        let ch = stacks[from].pop().unwrap();
-        stacks[to].push(ch);
-    }
-}
-
-fn part2(stacks: &mut Stacks, (n, from, to): Action) {
-    let crates: Vec<char> = (0..n).map(|_| stacks[from].pop().unwrap()).rev().collect();
-    stacks[to].extend(crates.into_iter().rev());
-}
-
-fn execute(mut stacks: Stacks, actions: &Vec<Action>, command: fn(&mut Stacks, Action)) -> String {
-    actions.iter().for_each(|i| command(&mut stacks, *i));
-    let result = stacks.iter().map(|s| s.last()).collect::<Option<String>>();
-    result.unwrap()
-}
-
 fn main() {
     let input = std::io::read_to_string(std::io::stdin()).unwrap();
     let (stacks_input, actions_input) = input.split_once("\n\n").unwrap();
-    let stacks = parse_stacks(&stacks_input);
+    let stacks = parse_stacks(stacks_input);
     let actions = parse_actions(actions_input);
 
-    println!("Part 1: {}", execute(stacks.clone(), &actions, part1));
-    println!("Part 2: {}", execute(stacks, &actions, part2));
+    if std::env::args().nth(1).as_deref() == Some("animate") {
+        let crane: &dyn Crane = match std::env::args().nth(2).as_deref() {
+            Some("9000") => &CrateMover9000,
+            Some("9001") | None => &CrateMover9001,
+            Some(other) => panic!("Unknown crane {other}"),
+        };
+        print!(
+            "{}",
+            crane::animate(stacks, &actions, crane).expect("Invalid move")
+        );
+        return;
+    }
+    println!(
+        "Part 1: {}",
+        crane::execute(stacks.clone(), &actions, &CrateMover9000).expect("Invalid move")
+    );
+    println!(
+        "Part 2: {}",
+        crane::execute(stacks, &actions, &CrateMover9001).expect("Invalid move")
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use day5::crane::Yard;
+    const TEST_INPUT: &str = include_str!("../test_input.txt");
+
+    fn parse(input: &str) -> (Stacks, Vec<Action>) {
+        let (stacks_input, actions_input) = input.split_once("\n\n").unwrap();
+        (parse_stacks(stacks_input), parse_actions(actions_input))
+    }
+    #[test]
+    fn test_part1() {
+        let (stacks, actions) = parse(TEST_INPUT);
+        assert_eq!(
+            crane::execute(stacks, &actions, &CrateMover9000),
+            Ok("CMZ".into())
+        );
+    }
+    #[test]
+    fn test_part2() {
+        let (stacks, actions) = parse(TEST_INPUT);
+        assert_eq!(
+            crane::execute(stacks, &actions, &CrateMover9001),
+            Ok("MCD".into())
+        );
+    }
+    #[test]
+    fn test_render_matches_input() {
+        let (stacks_input, _) = TEST_INPUT.split_once("\n\n").unwrap();
+        assert_eq!(
+            Yard::new(parse_stacks(stacks_input)).to_string(),
+            stacks_input
+        );
+    }
 }
@@ -1,22 +1,34 @@
-use itertools::Itertools;
+use marker::{markers, read_markers};
 
-fn first_n_unique(n: usize, input: &str) -> Option<usize> {
-    input
-        .as_bytes()
-        .windows(n)
-        .enumerate()
-        .find_map(|(i, xs)| xs.iter().all_unique().then_some(i + n))
-}
+mod marker;
 
 fn part1(input: &str) -> usize {
-    first_n_unique(4, input).expect("Start of packet not found")
+    markers(input.bytes(), 4)
+        .next()
+        .expect("Start of packet not found")
 }
 
 fn part2(input: &str) -> usize {
-    first_n_unique(14, input).expect("Start of message not found")
+    markers(input.bytes(), 14)
+        .next()
+        .expect("Start of message not found")
 }
 
 fn main() {
+    // Streams stdin and lists every marker, for inputs too big to keep around
+    if std::env::args().nth(1).as_deref() == Some("all") {
+        let size = std::env::args()
+            .nth(2)
+            .map_or(Some(4), |n| n.parse().ok().filter(|n| *n > 0));
+        let Some(size) = size else {
+            eprintln!("Marker size must be a positive number");
+            std::process::exit(1);
+        };
+        for position in read_markers(std::io::stdin(), size) {
+            println!("{}", position.expect("Read error"));
+        }
+        return;
+    }
     let input = std::io::read_to_string(std::io::stdin()).unwrap();
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
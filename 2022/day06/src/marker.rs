@@ -0,0 +1,104 @@
+use std::io::{self, BufReader, Read};
+
+// Sliding window over a byte stream that keeps a count of each byte in it and how many
+// bytes are in there more than once, so each new byte is checked in constant time
+pub struct Detector {
+    window: Vec<u8>,
+    counts: [usize; 256],
+    repeated: usize,
+    position: usize,
+}
+
+impl Detector {
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "Marker window can't be empty");
+        Detector {
+            window: vec![0; size],
+            counts: [0; 256],
+            repeated: 0,
+            position: 0,
+        }
+    }
+
+    // Returns the number of bytes read so far if the last `size` of them are all different
+    pub fn push(&mut self, byte: u8) -> Option<usize> {
+        let size = self.window.len();
+        let slot = self.position % size;
+        if self.position >= size {
+            let old = self.window[slot] as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] == 1 {
+                self.repeated -= 1;
+            }
+        }
+        self.window[slot] = byte;
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.repeated += 1;
+        }
+        self.position += 1;
+        (self.position >= size && self.repeated == 0).then_some(self.position)
+    }
+}
+
+// Every marker position in the bytes
+pub fn markers(bytes: impl IntoIterator<Item = u8>, size: usize) -> impl Iterator<Item = usize> {
+    let mut detector = Detector::new(size);
+    bytes.into_iter().filter_map(move |b| detector.push(b))
+}
+
+// Same for anything readable, read in buffered chunks as the markers are consumed
+pub fn read_markers<R: Read>(reader: R, size: usize) -> impl Iterator<Item = io::Result<usize>> {
+    let mut detector = Detector::new(size);
+    BufReader::new(reader).bytes().filter_map(move |b| match b {
+        Ok(b) => detector.push(b).map(Ok),
+        Err(e) => Some(Err(e)),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Checks every window from scratch
+    fn naive(input: &[u8], size: usize) -> Vec<usize> {
+        (size..=input.len())
+            .filter(|end| {
+                let window = &input[end - size..*end];
+                window
+                    .iter()
+                    .enumerate()
+                    .all(|(i, b)| !window[i + 1..].contains(b))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_all_markers() {
+        let input = b"mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        assert_eq!(
+            markers(input.iter().copied(), 4)
+                .take(3)
+                .collect::<Vec<_>>(),
+            [7, 8, 9]
+        );
+        for size in 1..=16 {
+            assert_eq!(
+                markers(input.iter().copied(), size).collect::<Vec<_>>(),
+                naive(input, size)
+            );
+        }
+        assert_eq!(markers(*b"aab", 1).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(markers(*b"aaaa", 2).count(), 0);
+    }
+    #[test]
+    fn test_read_markers() {
+        // Longer than the reader's buffer, with bytes beyond ASCII
+        let input: Vec<u8> = (0..20_000u32).map(|i| (i * i % 251) as u8).collect();
+        let read: Vec<usize> = read_markers(input.as_slice(), 14)
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(read, naive(&input, 14));
+        assert!(!read.is_empty());
+    }
+}
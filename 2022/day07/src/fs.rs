@@ -0,0 +1,292 @@
+use std::fmt;
+
+use crate::{Command, LsItem};
+
+pub type NodeId = usize;
+pub const ROOT: NodeId = 0;
+
+#[derive(Debug)]
+pub enum Kind {
+    File,
+    // Listed is set once `ls` has been run in the directory
+    Dir { children: Vec<NodeId>, listed: bool },
+}
+
+#[derive(Debug)]
+pub struct Node<'a> {
+    pub name: &'a str,
+    pub parent: Option<NodeId>,
+    // Total size of all files below, kept up to date as files are added
+    pub size: u32,
+    pub kind: Kind,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TranscriptError {
+    AboveRoot,
+    UnknownDirectory { path: String, name: String },
+    NotADirectory(String),
+    DuplicateListing(String),
+    ConflictingEntry(String),
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptError::AboveRoot => write!(f, "cd .. from the root directory"),
+            TranscriptError::UnknownDirectory { path, name } => {
+                write!(f, "cd into {name} which isn't listed in {path}")
+            }
+            TranscriptError::NotADirectory(path) => write!(f, "cd into file {path}"),
+            TranscriptError::DuplicateListing(path) => write!(f, "{path} listed twice"),
+            TranscriptError::ConflictingEntry(path) => {
+                write!(f, "{path} listed more than once in a directory")
+            }
+        }
+    }
+}
+
+pub struct FileSystem<'a> {
+    nodes: Vec<Node<'a>>,
+}
+
+impl<'a> FileSystem<'a> {
+    pub fn from_commands(commands: &[Command<'a>]) -> Result<Self, TranscriptError> {
+        let mut fs = FileSystem {
+            nodes: vec![Node {
+                name: "/",
+                parent: None,
+                size: 0,
+                kind: Kind::Dir {
+                    children: Vec::new(),
+                    listed: false,
+                },
+            }],
+        };
+        let mut cwd = ROOT;
+        for command in commands {
+            match command {
+                Command::Cd("/") => cwd = ROOT,
+                Command::Cd("..") => {
+                    cwd = fs.nodes[cwd].parent.ok_or(TranscriptError::AboveRoot)?
+                }
+                Command::Cd(name) => {
+                    let child =
+                        fs.child(cwd, name)
+                            .ok_or_else(|| TranscriptError::UnknownDirectory {
+                                path: fs.path(cwd),
+                                name: name.to_string(),
+                            })?;
+                    if let Kind::File = fs.nodes[child].kind {
+                        return Err(TranscriptError::NotADirectory(fs.path(child)));
+                    }
+                    cwd = child;
+                }
+                Command::Ls(items) => fs.list(cwd, items)?,
+            }
+        }
+        Ok(fs)
+    }
+
+    fn children(&self, id: NodeId) -> &[NodeId] {
+        match &self.nodes[id].kind {
+            Kind::Dir { children, .. } => children,
+            Kind::File => &[],
+        }
+    }
+
+    fn child(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        self.children(id)
+            .iter()
+            .copied()
+            .find(|c| self.nodes[*c].name == name)
+    }
+
+    fn list(&mut self, dir: NodeId, items: &[LsItem<'a>]) -> Result<(), TranscriptError> {
+        if let Kind::Dir { listed: true, .. } = self.nodes[dir].kind {
+            return Err(TranscriptError::DuplicateListing(self.path(dir)));
+        }
+        for item in items {
+            let (name, size, kind) = match item {
+                LsItem::File((size, name)) => (*name, *size, Kind::File),
+                LsItem::Dir(name) => (
+                    *name,
+                    0,
+                    Kind::Dir {
+                        children: Vec::new(),
+                        listed: false,
+                    },
+                ),
+            };
+            if let Some(existing) = self.child(dir, name) {
+                return Err(TranscriptError::ConflictingEntry(self.path(existing)));
+            }
+            let id = self.nodes.len();
+            self.nodes.push(Node {
+                name,
+                parent: Some(dir),
+                size,
+                kind,
+            });
+            if let Kind::Dir { children, .. } = &mut self.nodes[dir].kind {
+                children.push(id);
+            }
+            let mut ancestor = Some(dir);
+            while let Some(a) = ancestor {
+                self.nodes[a].size += size;
+                ancestor = self.nodes[a].parent;
+            }
+        }
+        if let Kind::Dir { listed, .. } = &mut self.nodes[dir].kind {
+            *listed = true;
+        }
+        Ok(())
+    }
+
+    pub fn size(&self, id: NodeId) -> u32 {
+        self.nodes[id].size
+    }
+
+    pub fn path(&self, id: NodeId) -> String {
+        let mut names = Vec::new();
+        let mut node = id;
+        while let Some(parent) = self.nodes[node].parent {
+            names.push(self.nodes[node].name);
+            node = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    pub fn directories(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len()).filter(|id| matches!(self.nodes[*id].kind, Kind::Dir { .. }))
+    }
+
+    // Listing in the style of `tree`, with sizes after the names
+    pub fn tree(&self) -> String {
+        let mut out = format!("/ ({})\n", self.size(ROOT));
+        self.tree_lines(ROOT, "", &mut out);
+        out
+    }
+
+    fn tree_lines(&self, id: NodeId, prefix: &str, out: &mut String) {
+        let children = self.children(id);
+        for (i, &child) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let node = &self.nodes[child];
+            let slash = if let Kind::Dir { .. } = node.kind {
+                "/"
+            } else {
+                ""
+            };
+            let branch = if last { "└── " } else { "├── " };
+            out.push_str(&format!(
+                "{prefix}{branch}{}{slash} ({})\n",
+                node.name, node.size
+            ));
+            let indent = if last { "    " } else { "│   " };
+            self.tree_lines(child, &format!("{prefix}{indent}"), out);
+        }
+    }
+
+    // Directories by size, largest first, like `du | sort -rn`
+    pub fn du(&self) -> Vec<(u32, String)> {
+        let mut report: Vec<_> = self
+            .directories()
+            .map(|id| (self.size(id), self.path(id)))
+            .collect();
+        report.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        report
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.json_node(ROOT, &mut out);
+        out
+    }
+
+    fn json_node(&self, id: NodeId, out: &mut String) {
+        let node = &self.nodes[id];
+        let mut name = String::new();
+        for c in node.name.chars() {
+            match c {
+                '"' => name.push_str("\\\""),
+                '\\' => name.push_str("\\\\"),
+                c if c.is_control() => name.push_str(&format!("\\u{:04x}", c as u32)),
+                c => name.push(c),
+            }
+        }
+        match &node.kind {
+            Kind::File => out.push_str(&format!(
+                r#"{{"name":"{name}","type":"file","size":{}}}"#,
+                node.size
+            )),
+            Kind::Dir { children, .. } => {
+                out.push_str(&format!(
+                    r#"{{"name":"{name}","type":"dir","size":{},"children":["#,
+                    node.size
+                ));
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    self.json_node(*child, out);
+                }
+                out.push_str("]}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use nom::Finish;
+
+    fn build(input: &str) -> Result<FileSystem<'_>, TranscriptError> {
+        let (_, commands) = parse(input).finish().expect("Parse error");
+        // Names borrow from the input, so the commands can go once the tree is built
+        FileSystem::from_commands(&commands)
+    }
+
+    #[test]
+    fn test_tree_and_du() {
+        let fs = build("$ cd /\n$ ls\ndir a\n10 b\n$ cd a\n$ ls\n5 \"c\"\ndir d\n").unwrap();
+        assert_eq!(
+            fs.tree(),
+            "/ (15)\n├── a/ (5)\n│   ├── \"c\" (5)\n│   └── d/ (0)\n└── b (10)\n"
+        );
+        assert_eq!(
+            fs.du(),
+            [(15, "/".into()), (5, "/a".into()), (0, "/a/d".into())]
+        );
+        assert_eq!(
+            fs.to_json(),
+            r#"{"name":"/","type":"dir","size":15,"children":[{"name":"a","type":"dir","size":5,"children":[{"name":"\"c\"","type":"file","size":5},{"name":"d","type":"dir","size":0,"children":[]}]},{"name":"b","type":"file","size":10}]}"#
+        );
+    }
+    #[test]
+    fn test_errors() {
+        assert_eq!(build("$ cd ..\n").err(), Some(TranscriptError::AboveRoot));
+        assert_eq!(
+            build("$ cd /\n$ ls\ndir a\n$ cd b\n").err(),
+            Some(TranscriptError::UnknownDirectory {
+                path: "/".into(),
+                name: "b".into()
+            })
+        );
+        assert_eq!(
+            build("$ ls\n1 a\n$ cd a\n").err(),
+            Some(TranscriptError::NotADirectory("/a".into()))
+        );
+        assert_eq!(
+            build("$ ls\ndir a\n$ cd a\n$ ls\n$ cd ..\n$ cd a\n$ ls\n").err(),
+            Some(TranscriptError::DuplicateListing("/a".into()))
+        );
+        assert_eq!(
+            build("$ ls\ndir a\n1 a\n").err(),
+            Some(TranscriptError::ConflictingEntry("/a".into()))
+        );
+    }
+}
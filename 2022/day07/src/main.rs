@@ -7,7 +7,9 @@ use nom::{
     sequence::{preceded, separated_pair, terminated},
     Finish, IResult,
 };
-use std::{collections::HashMap, path::PathBuf};
+
+mod fs;
+use fs::FileSystem;
 
 #[derive(Debug)]
 enum LsItem<'a> {
@@ -20,13 +22,7 @@ enum Command<'a> {
     Ls(Vec<LsItem<'a>>),
 }
 
-#[derive(Default, Debug)]
-struct State<'a> {
-    pwd: PathBuf,
-    files: HashMap<PathBuf, Vec<(u32, &'a str)>>,
-}
-
-fn parse(input: &str) -> IResult<&str, Vec<Command>> {
+fn parse(input: &str) -> IResult<&str, Vec<Command<'_>>> {
     let parse_cd = terminated(preceded(tag("$ cd "), ncc::not_line_ending), ncc::line_ending);
     let parse_file = map(separated_pair(ncc::u32, ncc::space1, ncc::not_line_ending), LsItem::File);
     let parse_dir = map(preceded(tag("dir "), ncc::not_line_ending), LsItem::Dir);
@@ -35,48 +31,25 @@ fn parse(input: &str) -> IResult<&str, Vec<Command>> {
     all_consuming(many0(alt((map(parse_cd, Command::Cd), map(parse_ls, Command::Ls)))))(input)
 }
 
-fn execute<'s>(state: &mut State<'s>, command: &'s Command) {
-    match command {
-        Command::Cd("/") => state.pwd = "/".into(),
-        Command::Cd("..") => { state.pwd.pop(); }
-        Command::Cd(dir) => state.pwd.push(dir),
-        Command::Ls(items) => state.files
-            .entry(state.pwd.clone())
-            .or_insert(Vec::new())
-            .extend(items.iter().filter_map(|item| match item {
-                LsItem::File(f) => Some(f),
-                LsItem::Dir(_) => None,
-            })),
-    };
-}
-
-fn directory_size(state: &State, path: &PathBuf) -> u32 {
-    state
-        .files
-        .iter()
-        .filter(|(p, _)| p.starts_with(path))
-        .flat_map(|(_, files)| files.iter().map(|(size, _)| size))
-        .sum()
-}
-
-fn part1(state: &State) -> u32 {
+fn part1(fs: &FileSystem) -> u32 {
     const MAX_SIZE: u32 = 100_000;
-    state.files.keys()
-        .map(|k| directory_size(&state, k))
+    fs.directories()
+        .map(|id| fs.size(id))
         .filter(|size| *size <= MAX_SIZE)
         .sum()
 }
 
-fn part2(state: &State) -> Option<u32> {
+fn part2(fs: &FileSystem) -> Option<u32> {
     const TOTAL_SPACE: u32 = 70_000_000;
     const NEED_SPACE: u32 = 30_000_000;
 
-    let used_space = directory_size(&state, &"/".into());
-    let unused_space = TOTAL_SPACE - used_space;
-    let to_delete = NEED_SPACE - unused_space;
+    let used_space = fs.size(fs::ROOT);
+    // Nothing to delete helps when the files don't even fit on the disk
+    let unused_space = TOTAL_SPACE.checked_sub(used_space)?;
+    let to_delete = NEED_SPACE.saturating_sub(unused_space);
 
-    state.files.keys()
-        .map(|k| directory_size(&state, k))
+    fs.directories()
+        .map(|id| fs.size(id))
         .filter(|size| *size >= to_delete)
         .min()
 }
@@ -84,11 +57,53 @@ fn part2(state: &State) -> Option<u32> {
 fn main() {
     let input = std::io::read_to_string(std::io::stdin()).unwrap();
     let (_, commands) = parse(&input).finish().expect("Error parsing commands");
-    let state = commands.iter().fold(State::default(), |mut state, c| {
-        execute(&mut state, c);
-        state
-    });
+    let fs = match FileSystem::from_commands(&commands) {
+        Ok(fs) => fs,
+        Err(e) => {
+            eprintln!("Invalid transcript: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    match std::env::args().nth(1).as_deref() {
+        Some("tree") => print!("{}", fs.tree()),
+        Some("du") => fs.du().iter().for_each(|(size, path)| println!("{size}\t{path}")),
+        Some("json") => println!("{}", fs.to_json()),
+        _ => {
+            println!("Part 1: {}", part1(&fs));
+            println!("Part 2: {}", part2(&fs).expect("No suitable directory found"));
+        }
+    }
+}
 
-    println!("Part 1: {}", part1(&state));
-    println!("Part 2: {}", part2(&state).expect("No suitable directory found"));
+#[cfg(test)]
+mod test {
+    use super::*;
+    const TEST_INPUT: &str = include_str!("../test_input.txt");
+    const PUZZLE_INPUT: &str = include_str!("../puzzle_input.txt");
+    #[test]
+    fn test_part1() {
+        let (_, commands) = parse(TEST_INPUT).finish().expect("Parse error");
+        let fs = FileSystem::from_commands(&commands).expect("Invalid transcript");
+        assert_eq!(part1(&fs), 95437);
+    }
+    #[test]
+    fn test_part2() {
+        let (_, commands) = parse(TEST_INPUT).finish().expect("Parse error");
+        let fs = FileSystem::from_commands(&commands).expect("Invalid transcript");
+        assert_eq!(part2(&fs), Some(24933642));
+    }
+    #[test]
+    fn test_part2_overfull() {
+        let (_, commands) = parse("$ cd /\n$ ls\n70000001 big\n").finish().expect("Parse error");
+        let fs = FileSystem::from_commands(&commands).expect("Invalid transcript");
+        assert_eq!(part2(&fs), None);
+    }
+    #[test]
+    fn test_puzzle() {
+        let (_, commands) = parse(PUZZLE_INPUT).finish().expect("Parse error");
+        let fs = FileSystem::from_commands(&commands).expect("Invalid transcript");
+        assert_eq!(part1(&fs), 1307902);
+        assert_eq!(part2(&fs), Some(7068748));
+    }
 }
@@ -0,0 +1,121 @@
+use crate::Trees;
+
+pub const UP: usize = 0;
+pub const DOWN: usize = 1;
+pub const LEFT: usize = 2;
+pub const RIGHT: usize = 3;
+
+// Visibility and viewing distance for every tree, indexed by UP, DOWN, LEFT and RIGHT
+pub struct Forest {
+    pub visible: Vec<Vec<[bool; 4]>>,
+    pub distance: Vec<Vec<[usize; 4]>>,
+}
+
+// Looks back along a line of trees from each one. The stack holds the trees that can
+// still block the view, tallest at the bottom, so each tree is pushed and popped once.
+fn sweep(heights: &[u32]) -> Vec<(bool, usize)> {
+    let mut stack: Vec<usize> = Vec::new();
+    heights
+        .iter()
+        .enumerate()
+        .map(|(i, h)| {
+            while stack.last().is_some_and(|j| heights[*j] < *h) {
+                stack.pop();
+            }
+            let result = match stack.last() {
+                Some(j) => (false, i - j),
+                None => (true, i),
+            };
+            stack.push(i);
+            result
+        })
+        .collect()
+}
+
+impl Forest {
+    // None unless every row is as long as the first
+    pub fn new(trees: &Trees) -> Option<Self> {
+        let height = trees.len();
+        let width = trees.first().map_or(0, |row| row.len());
+        if trees.iter().any(|row| row.len() != width) {
+            return None;
+        }
+        let mut visible = vec![vec![[false; 4]; width]; height];
+        let mut distance = vec![vec![[0; 4]; width]; height];
+        let mut record = |cells: &[(usize, usize)], heights: Vec<u32>, dir: usize| {
+            for ((x, y), (v, d)) in cells.iter().zip(sweep(&heights)) {
+                visible[*y][*x][dir] = v;
+                distance[*y][*x][dir] = d;
+            }
+        };
+        for (y, row) in trees.iter().enumerate() {
+            let mut cells: Vec<_> = (0..width).map(|x| (x, y)).collect();
+            record(&cells, row.clone(), LEFT);
+            cells.reverse();
+            record(&cells, row.iter().rev().copied().collect(), RIGHT);
+        }
+        for x in 0..width {
+            let mut cells: Vec<_> = (0..height).map(|y| (x, y)).collect();
+            record(&cells, trees.iter().map(|row| row[x]).collect(), UP);
+            cells.reverse();
+            record(&cells, trees.iter().rev().map(|row| row[x]).collect(), DOWN);
+        }
+        Some(Forest { visible, distance })
+    }
+
+    pub fn visible_count(&self) -> usize {
+        self.visible
+            .iter()
+            .flatten()
+            .filter(|v| v.iter().any(|v| *v))
+            .count()
+    }
+
+    pub fn scenic_scores(&self) -> Vec<Vec<usize>> {
+        self.distance
+            .iter()
+            .map(|row| row.iter().map(|d| d.iter().product()).collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_grids() {
+        let forest = Forest::new(&parse("30373\n25512\n65332\n33549\n35390").unwrap()).unwrap();
+        assert_eq!(forest.distance[3][2], [2, 1, 2, 2]);
+        assert_eq!(forest.distance[1][2], [1, 2, 1, 2]);
+        assert_eq!(forest.visible[1][1], [true, false, true, false]);
+        assert_eq!(forest.visible[2][2], [false; 4]);
+        assert_eq!(forest.scenic_scores()[3][2], 8);
+        assert_eq!(forest.scenic_scores()[0][0], 0);
+    }
+    #[test]
+    fn test_tall_trees() {
+        let trees = parse("10 200 30\n12 11 40\n9 150 250").unwrap();
+        let forest = Forest::new(&trees).unwrap();
+        assert_eq!(forest.visible_count(), 8);
+        assert_eq!(forest.distance[1][1], [1, 1, 1, 1]);
+        assert_eq!(forest.distance[2][2], [2, 0, 2, 0]);
+        assert!(!forest.visible[1][1].iter().any(|v| *v));
+        assert!(Forest::new(&vec![vec![1, 2], vec![3]]).is_none());
+    }
+    #[test]
+    fn test_sweep() {
+        assert_eq!(
+            sweep(&[5, 1, 3, 3, 7, 2]),
+            [
+                (true, 0),
+                (false, 1),
+                (false, 2),
+                (false, 1),
+                (true, 4),
+                (false, 1)
+            ]
+        );
+    }
+}
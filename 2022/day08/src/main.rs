@@ -1,90 +1,64 @@
-use itertools::Itertools;
-use bittle::{Bits, BitsMut};
-type Trees = Vec<Vec<u8>>;
+use forest::Forest;
 
-fn ray<'a>(
-    x: i32,
-    y: i32,
-    dx: i32,
-    dy: i32,
-    trees: &'a Trees,
-) -> impl Iterator<Item = (usize, usize, &'a u8)> {
-    (0..)
-        .map(move |i| ((x + i * dx) as usize, (y + i * dy) as usize))
-        .take_while(|(x, y)| *y < trees.len() && *x < trees[*y].len())
-        .map(|(x, y)| (x, y, &trees[y][x]))
-}
-fn scenic_score((y, x): (usize, usize), trees: &Trees) -> usize {
-    if y == 0 || x == 0 || y == trees.len() - 1 || x == trees[y].len() - 1 {
-        0
-    } else {
-        [
-            (x, y - 1, 0, -1),
-            (x, y + 1, 0, 1),
-            (x - 1, y, -1, 0),
-            (x + 1, y, 1, 0),
-        ]
-        .map(|(xx, yy, dx, dy)| {
-            ray(xx as i32, yy as i32, dx, dy, trees)
-                .map(|(_, _, t)| *t)
-                .fold((0, 0), |(tallest, seen), t| {
-                    (tallest >= trees[y][x])
-                        .then_some((tallest, seen))
-                        .unwrap_or((t.max(tallest), seen + 1))
-                })
-                .1
-        })
-        .into_iter()
-        .product()
-    }
-}
-fn part1(trees: &Trees) -> u32 {
-    let height = trees.len() as i32;
-    let width = trees.iter().map(|row| row.len()).max().unwrap() as i32;
-    let mut visible = vec![0u32; (width * height) as usize / 32 + 1];
-    let views = [
-        (0..1, 0..height, 1, 0),
-        (width - 1..width, 0..height, -1, 0),
-        (0..width, 0..1, 0, 1),
-        (0..width, height - 1..height, 0, -1),
-    ];
-    for (xs, ys, dx, dy) in views {
-        xs.cartesian_product(ys).for_each(|(x, y)| {
-            visible.set_bit((x + y * width as i32) as u32);
-            let mut r = ray(x, y, dx, dy, trees);
-            let (_, _, mut h) = r.next().unwrap();
-            r.for_each(|(x, y, t)| {
-                if t > h {
-                    visible.set_bit((x + y * width as usize) as u32);
-                    h = t;
-                }
-            });
-        });
-    }
-    visible.count_ones()
+mod forest;
+
+type Trees = Vec<Vec<u32>>;
+
+fn part1(trees: &Trees) -> usize {
+    Forest::new(trees).expect("Forest is not rectangular").visible_count()
 }
 
 fn part2(trees: &Trees) -> usize {
-    (0..trees.len())
-        .cartesian_product(0..trees[0].len())
-        .map(|pos| scenic_score(pos, trees))
+    Forest::new(trees)
+        .expect("Forest is not rectangular")
+        .scenic_scores()
+        .into_iter()
+        .flatten()
         .max()
         .unwrap()
 }
 
-fn parse(input: &str) -> Option<Trees> {
-    input
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Format {
+    // One digit per tree, like the puzzle input
+    Digits,
+    // Whitespace separated numbers, for trees taller than 9
+    Numbers,
+}
+
+// Numbers only when some row has a separator inside it, so a single column of tall
+// trees has to ask for `Format::Numbers` explicitly
+fn guess_format(input: &str) -> Format {
+    if input.lines().any(|l| l.trim().contains(char::is_whitespace)) {
+        Format::Numbers
+    } else {
+        Format::Digits
+    }
+}
+
+fn parse_as(input: &str, format: Format) -> Option<Trees> {
+    let trees: Trees = input
         .lines()
-        .map(|l| {
-            l.chars()
-                .map(|ch| ch.to_digit(10).map(|d| d as u8))
-                .collect::<Option<Vec<_>>>()
+        .map(|l| match format {
+            Format::Digits => l.trim().chars().map(|ch| ch.to_digit(10)).collect::<Option<Vec<_>>>(),
+            Format::Numbers => l.split_whitespace().map(|n| n.parse().ok()).collect::<Option<Vec<_>>>(),
         })
-        .collect()
+        .collect::<Option<_>>()?;
+    trees.iter().all(|row| row.len() == trees[0].len()).then_some(trees)
+}
+
+fn parse(input: &str) -> Option<Trees> {
+    parse_as(input, guess_format(input))
 }
+
 fn main() {
     let input = std::io::read_to_string(std::io::stdin()).unwrap();
-    let trees = parse(&input).expect("parse error");
+    let trees = match std::env::args().nth(1).as_deref() {
+        Some("digits") => parse_as(&input, Format::Digits),
+        Some("numbers") => parse_as(&input, Format::Numbers),
+        _ => parse(&input),
+    }
+    .expect("parse error");
     println!("Part 1: {}", part1(&trees));
     println!("Part 2: {}", part2(&trees));
 }
@@ -106,4 +80,16 @@ mod test {
             8
         );
     }
+    #[test]
+    fn test_parse_modes() {
+        // A single column is ambiguous and read as digits unless told otherwise
+        assert_eq!(parse("12\n34"), Some(vec![vec![1, 2], vec![3, 4]]));
+        assert_eq!(parse_as("12\n34", Format::Numbers), Some(vec![vec![12], vec![34]]));
+        assert_eq!(parse("12\n5\n300"), None);
+        assert_eq!(parse_as("12\n5\n300", Format::Numbers), Some(vec![vec![12], vec![5], vec![300]]));
+        assert_eq!(parse("30373 \n25512"), Some(vec![vec![3, 0, 3, 7, 3], vec![2, 5, 5, 1, 2]]));
+        assert_eq!(parse("30373\n25512 "), parse("30373\n25512"));
+        assert_eq!(parse("1 2\n345"), None);
+        assert_eq!(parse("1 2\n3"), None);
+    }
 }
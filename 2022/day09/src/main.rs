@@ -2,27 +2,11 @@ use nom::{
     branch::alt, character::complete as ncc, combinator::value, multi::separated_list0,
     sequence::separated_pair,
 };
+use rope::{Move, Moves, Rope};
 use std::collections::HashSet;
+use std::io::Write;
 
-#[derive(Copy, Clone)]
-enum Move {
-    Left,
-    Right,
-    Up,
-    Down,
-}
-type Moves = Vec<(Move, u32)>;
-
-impl From<Move> for (i32, i32) {
-    fn from(m: Move) -> Self {
-        match m {
-            Move::Left => (-1, 0),
-            Move::Right => (1, 0),
-            Move::Up => (0, -1),
-            Move::Down => (0, 1),
-        }
-    }
-}
+mod rope;
 
 fn parse(input: &str) -> nom::IResult<&str, Moves> {
     let direction = alt((
@@ -30,6 +14,8 @@ fn parse(input: &str) -> nom::IResult<&str, Moves> {
         value(Move::Down, ncc::char('D')),
         value(Move::Left, ncc::char('L')),
         value(Move::Right, ncc::char('R')),
+        value(Move::Forward, ncc::char('F')),
+        value(Move::Backward, ncc::char('B')),
     ));
     separated_list0(
         ncc::line_ending,
@@ -37,46 +23,52 @@ fn parse(input: &str) -> nom::IResult<&str, Moves> {
     )(input)
 }
 
-fn follow((hx, hy): (i32, i32), (tx, ty): (i32, i32)) -> (i32, i32) {
-    if hx.abs_diff(tx) <= 1 && hy.abs_diff(ty) <= 1 {
-        (tx, ty)
+fn tail_positions(moves: &Moves, knots: usize) -> usize {
+    if rope::dimensions(moves) == 3 {
+        Rope::<3>::new(knots).tail_trail(moves).len()
     } else {
-        (tx + (hx - tx).signum(), ty + (hy - ty).signum())
+        Rope::<2>::new(knots).tail_trail(moves).len()
     }
 }
 
-fn tail_positions<const PARTS: usize>(moves: &Moves) -> usize {
-    moves
-        .into_iter()
-        .flat_map(|(m, n)| (0..*n).map(move |_| (*m).into()))
-        .scan([(0, 0); PARTS], |state, (dx, dy)| {
-            state[0] = (state[0].0 + dx, state[0].1 + dy);
-            let head = state[0];
-            state
-                .iter_mut()
-                .fold(head, |h: (i32, i32), x| {
-                    *x = follow(h, *x);
-                    *x
-                })
-                .into()
-        })
-        .collect::<HashSet<_>>()
-        .len()
-}
-
 fn part1(moves: &Moves) -> usize {
-    tail_positions::<2>(moves)
+    tail_positions(moves, 2)
 }
 
 fn part2(moves: &Moves) -> usize {
-    tail_positions::<10>(moves)
+    tail_positions(moves, 10)
 }
 
 fn main() {
     let input = std::io::read_to_string(std::io::stdin()).unwrap();
     let (_, moves) = parse(&input).expect("Error parsing input");
-    println!("Part 1: {}", part1(&moves));
-    println!("Part 2: {}", part2(&moves));
+    let mut args = std::env::args().skip(1);
+    let mode = args.next();
+    let knots = args
+        .next()
+        .map_or(10, |n| n.parse().expect("Invalid knot count"));
+    match mode.as_deref() {
+        Some("trail") | Some("ppm") => {
+            let mut rope = Rope::<3>::new(knots);
+            let trail: HashSet<_> = rope
+                .tail_trail(&moves)
+                .into_iter()
+                .map(rope::project)
+                .collect();
+            let knots: Vec<_> = rope.knots().iter().copied().map(rope::project).collect();
+            if mode.as_deref() == Some("trail") {
+                print!("{}", rope::render_text(&trail, &knots));
+            } else {
+                std::io::stdout()
+                    .write_all(&rope::render_ppm(&trail, &knots, 4))
+                    .unwrap();
+            }
+        }
+        _ => {
+            println!("Part 1: {}", part1(&moves));
+            println!("Part 2: {}", part2(&moves));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -92,4 +84,37 @@ mod test {
         let (_, moves) = parse("R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20").unwrap();
         assert_eq!(part2(&moves), 36);
     }
+    #[test]
+    fn test_render_trail() {
+        let (_, moves) = parse("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2").unwrap();
+        let trail = Rope::<2>::new(2).tail_trail(&moves);
+        assert_eq!(
+            rope::render_text(&trail, &[]),
+            "..##.\n...##\n.####\n....#\ns###.\n"
+        );
+    }
+    #[test]
+    fn test_steps() {
+        let (_, moves) = parse("R 4\nU 4").unwrap();
+        let steps: Vec<_> = Rope::<2>::new(3).steps(&moves).collect();
+        assert_eq!(steps.len(), 8);
+        assert_eq!(steps[3], vec![[4, 0], [3, 0], [2, 0]]);
+        assert_eq!(steps[7], vec![[4, -4], [4, -3], [4, -2]]);
+    }
+    #[test]
+    fn test_3d() {
+        let (_, moves) = parse("F 3\nR 2\nB 1").unwrap();
+        let knots = Rope::<3>::new(2).steps(&moves).last().unwrap();
+        assert_eq!(knots, vec![[2, 0, -2], [1, 0, -3]]);
+        assert_eq!(Rope::<3>::new(10).tail_trail(&moves).len(), 1);
+    }
+    #[test]
+    fn test_3d_parts() {
+        let (_, moves) = parse("F 3\nR 2\nB 1").unwrap();
+        assert_eq!(rope::dimensions(&moves), 3);
+        assert_eq!(part1(&moves), 4);
+        assert_eq!(part2(&moves), 1);
+        let (_, flat) = parse("R 4\nU 4").unwrap();
+        assert_eq!(rope::dimensions(&flat), 2);
+    }
 }
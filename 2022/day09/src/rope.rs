@@ -0,0 +1,161 @@
+use std::collections::HashSet;
+
+pub type Position<const D: usize> = [i32; D];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Move {
+    Left,
+    Right,
+    Up,
+    Down,
+    Forward,
+    Backward,
+}
+pub type Moves = Vec<(Move, u32)>;
+
+// Forward and backward moves need a third axis
+pub fn dimensions(moves: &[(Move, u32)]) -> usize {
+    if moves
+        .iter()
+        .any(|(m, _)| matches!(m, Move::Forward | Move::Backward))
+    {
+        3
+    } else {
+        2
+    }
+}
+
+impl Move {
+    pub fn delta<const D: usize>(self) -> Position<D> {
+        let (axis, amount) = match self {
+            Move::Left => (0, -1),
+            Move::Right => (0, 1),
+            Move::Up => (1, -1),
+            Move::Down => (1, 1),
+            Move::Forward => (2, -1),
+            Move::Backward => (2, 1),
+        };
+        assert!(axis < D, "{self:?} needs at least {} dimensions", axis + 1);
+        let mut delta = [0; D];
+        delta[axis] = amount;
+        delta
+    }
+}
+
+pub fn follow<const D: usize>(head: Position<D>, tail: Position<D>) -> Position<D> {
+    if head.iter().zip(tail).all(|(h, t)| h.abs_diff(t) <= 1) {
+        tail
+    } else {
+        std::array::from_fn(|i| tail[i] + (head[i] - tail[i]).signum())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Rope<const D: usize> {
+    knots: Vec<Position<D>>,
+}
+
+impl<const D: usize> Rope<D> {
+    pub fn new(knots: usize) -> Self {
+        assert!(knots > 0, "A rope needs at least one knot");
+        Rope {
+            knots: vec![[0; D]; knots],
+        }
+    }
+    pub fn knots(&self) -> &[Position<D>] {
+        &self.knots
+    }
+    pub fn tail(&self) -> Position<D> {
+        *self.knots.last().unwrap()
+    }
+    pub fn step(&mut self, delta: Position<D>) {
+        let head = &mut self.knots[0];
+        *head = std::array::from_fn(|i| head[i] + delta[i]);
+        for i in 1..self.knots.len() {
+            self.knots[i] = follow(self.knots[i - 1], self.knots[i]);
+        }
+    }
+    // Snapshot of every knot position after each single step of the moves
+    pub fn steps<'a>(
+        &'a mut self,
+        moves: &'a [(Move, u32)],
+    ) -> impl Iterator<Item = Vec<Position<D>>> + 'a {
+        moves
+            .iter()
+            .flat_map(|&(m, n)| (0..n).map(move |_| m.delta::<D>()))
+            .map(move |delta| {
+                self.step(delta);
+                self.knots.clone()
+            })
+    }
+    pub fn tail_trail(&mut self, moves: &[(Move, u32)]) -> HashSet<Position<D>> {
+        let start = self.tail();
+        std::iter::once(start)
+            .chain(self.steps(moves).map(|knots| *knots.last().unwrap()))
+            .collect()
+    }
+}
+
+pub fn project<const D: usize>(position: Position<D>) -> Position<2> {
+    [position[0], position[1]]
+}
+
+fn bounds<'a>(positions: impl Iterator<Item = &'a Position<2>>) -> (Position<2>, Position<2>) {
+    positions.fold(([0, 0], [0, 0]), |([x0, y0], [x1, y1]), &[x, y]| {
+        ([x0.min(x), y0.min(y)], [x1.max(x), y1.max(y)])
+    })
+}
+
+fn knot_char(i: usize) -> char {
+    match i {
+        0 => 'H',
+        1..=9 => char::from_digit(i as u32, 10).unwrap(),
+        _ => '+',
+    }
+}
+
+// Same notation as the puzzle description: knots over the start marker over the trail
+pub fn render_text(trail: &HashSet<Position<2>>, knots: &[Position<2>]) -> String {
+    let ([x0, y0], [x1, y1]) = bounds(trail.iter().chain(knots));
+    let mut out = String::new();
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let c = if let Some(i) = knots.iter().position(|&k| k == [x, y]) {
+                knot_char(i)
+            } else if [x, y] == [0, 0] {
+                's'
+            } else if trail.contains(&[x, y]) {
+                '#'
+            } else {
+                '.'
+            };
+            out.push(c);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// Binary PPM with trail in white, start in green and knots in red
+pub fn render_ppm(trail: &HashSet<Position<2>>, knots: &[Position<2>], scale: usize) -> Vec<u8> {
+    let ([x0, y0], [x1, y1]) = bounds(trail.iter().chain(knots));
+    let width = (x1 - x0 + 1) as usize * scale;
+    let height = (y1 - y0 + 1) as usize * scale;
+    let mut out = format!("P6\n{width} {height}\n255\n").into_bytes();
+    for py in 0..height {
+        for px in 0..width {
+            let p = [x0 + (px / scale) as i32, y0 + (py / scale) as i32];
+            let color = if knots.contains(&p) {
+                [255, 0, 0]
+            } else if p == [0, 0] {
+                [0, 255, 0]
+            } else if trail.contains(&p) {
+                [255, 255, 255]
+            } else {
+                [0, 0, 0]
+            };
+            out.extend(color);
+        }
+    }
+    out
+}
@@ -0,0 +1,545 @@
+use std::collections::HashMap;
+use std::fmt;
+
+pub const REGISTERS: [&str; 4] = ["x", "y", "z", "w"];
+pub const X: usize = 0;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Machine {
+    pub registers: [i32; REGISTERS.len()],
+    pub ip: usize,
+    pub cycle: u64,
+}
+
+pub trait Instruction: fmt::Display + fmt::Debug {
+    fn cycles(&self) -> u32;
+    // Called after the last cycle of the instruction with `ip` already pointing to the next one
+    fn execute(&self, machine: &mut Machine) -> Result<(), Fault>;
+}
+
+// Something the machine can't go on from, arithmetic wraps around instead
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    JumpOutOfRange { ip: usize, target: i64 },
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fault::JumpOutOfRange { ip, target } => {
+                write!(f, "jump from {ip} to {target} is outside the program")
+            }
+        }
+    }
+}
+
+pub type Program = Vec<Box<dyn Instruction>>;
+pub type Decoder = fn(&[&str]) -> Result<Box<dyn Instruction>, String>;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Operand {
+    Register(usize),
+    Immediate(i32),
+}
+
+impl Operand {
+    fn parse(s: &str) -> Result<Self, String> {
+        if let Some(r) = REGISTERS.iter().position(|&name| name == s) {
+            Ok(Operand::Register(r))
+        } else {
+            s.parse()
+                .map(Operand::Immediate)
+                .map_err(|_| format!("Invalid operand '{s}'"))
+        }
+    }
+    fn value(&self, machine: &Machine) -> i32 {
+        match *self {
+            Operand::Register(r) => machine.registers[r],
+            Operand::Immediate(v) => v,
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Operand::Register(r) => write!(f, "{}", REGISTERS[r]),
+            Operand::Immediate(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+fn register(s: &str) -> Result<usize, String> {
+    match Operand::parse(s)? {
+        Operand::Register(r) => Ok(r),
+        Operand::Immediate(_) => Err(format!("Expected a register, got '{s}'")),
+    }
+}
+
+fn immediate(s: &str) -> Result<i32, String> {
+    s.parse()
+        .map_err(|_| format!("Expected a number, got '{s}'"))
+}
+
+fn operands<'a, const N: usize>(args: &[&'a str]) -> Result<[&'a str; N], String> {
+    args.try_into()
+        .map_err(|_| format!("Expected {N} operands, got {}", args.len()))
+}
+
+#[derive(Debug)]
+pub struct Noop;
+#[derive(Debug)]
+pub struct AddX(pub i32);
+#[derive(Debug)]
+pub struct Set(pub usize, pub Operand);
+#[derive(Debug)]
+pub struct Add(pub usize, pub Operand);
+#[derive(Debug)]
+pub struct Mul(pub usize, pub Operand);
+#[derive(Debug)]
+pub struct Jnz(pub Operand, pub i32);
+
+impl Instruction for Noop {
+    fn cycles(&self) -> u32 {
+        1
+    }
+    fn execute(&self, _machine: &mut Machine) -> Result<(), Fault> {
+        Ok(())
+    }
+}
+impl fmt::Display for Noop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "noop")
+    }
+}
+
+impl Instruction for AddX {
+    fn cycles(&self) -> u32 {
+        2
+    }
+    fn execute(&self, machine: &mut Machine) -> Result<(), Fault> {
+        machine.registers[X] = machine.registers[X].wrapping_add(self.0);
+        Ok(())
+    }
+}
+impl fmt::Display for AddX {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "addx {}", self.0)
+    }
+}
+
+impl Instruction for Set {
+    fn cycles(&self) -> u32 {
+        1
+    }
+    fn execute(&self, machine: &mut Machine) -> Result<(), Fault> {
+        machine.registers[self.0] = self.1.value(machine);
+        Ok(())
+    }
+}
+impl fmt::Display for Set {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "set {} {}", REGISTERS[self.0], self.1)
+    }
+}
+
+impl Instruction for Add {
+    fn cycles(&self) -> u32 {
+        2
+    }
+    fn execute(&self, machine: &mut Machine) -> Result<(), Fault> {
+        machine.registers[self.0] = machine.registers[self.0].wrapping_add(self.1.value(machine));
+        Ok(())
+    }
+}
+impl fmt::Display for Add {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "add {} {}", REGISTERS[self.0], self.1)
+    }
+}
+
+impl Instruction for Mul {
+    fn cycles(&self) -> u32 {
+        3
+    }
+    fn execute(&self, machine: &mut Machine) -> Result<(), Fault> {
+        machine.registers[self.0] = machine.registers[self.0].wrapping_mul(self.1.value(machine));
+        Ok(())
+    }
+}
+impl fmt::Display for Mul {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "mul {} {}", REGISTERS[self.0], self.1)
+    }
+}
+
+impl Instruction for Jnz {
+    fn cycles(&self) -> u32 {
+        1
+    }
+    // Offset is relative to the jnz instruction itself, the CPU checks the upper end
+    fn execute(&self, machine: &mut Machine) -> Result<(), Fault> {
+        if self.0.value(machine) != 0 {
+            let ip = machine.ip - 1;
+            let target = ip as i64 + self.1 as i64;
+            machine.ip =
+                usize::try_from(target).map_err(|_| Fault::JumpOutOfRange { ip, target })?;
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for Jnz {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "jnz {} {}", self.0, self.1)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InstructionSet {
+    decoders: HashMap<&'static str, Decoder>,
+}
+
+impl InstructionSet {
+    pub fn with(mut self, mnemonic: &'static str, decoder: Decoder) -> Self {
+        self.decoders.insert(mnemonic, decoder);
+        self
+    }
+    // Just the instructions of the puzzle
+    pub fn elf() -> Self {
+        InstructionSet::default()
+            .with("noop", |args| {
+                operands::<0>(args)?;
+                Ok(Box::new(Noop))
+            })
+            .with("addx", |args| {
+                let [v] = operands(args)?;
+                Ok(Box::new(AddX(immediate(v)?)))
+            })
+    }
+    pub fn extended() -> Self {
+        InstructionSet::elf()
+            .with("set", |args| {
+                let [r, v] = operands(args)?;
+                Ok(Box::new(Set(register(r)?, Operand::parse(v)?)))
+            })
+            .with("add", |args| {
+                let [r, v] = operands(args)?;
+                Ok(Box::new(Add(register(r)?, Operand::parse(v)?)))
+            })
+            .with("mul", |args| {
+                let [r, v] = operands(args)?;
+                Ok(Box::new(Mul(register(r)?, Operand::parse(v)?)))
+            })
+            .with("jnz", |args| {
+                let [c, offset] = operands(args)?;
+                Ok(Box::new(Jnz(Operand::parse(c)?, immediate(offset)?)))
+            })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+// One instruction per line, `;` starts a comment
+pub fn assemble(source: &str, set: &InstructionSet) -> Result<Program, AsmError> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split(';').next().unwrap().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(line, text)| {
+            let mut words = text.split_whitespace();
+            let mnemonic = words.next().unwrap();
+            let args: Vec<_> = words.collect();
+            let decoder = set.decoders.get(mnemonic).ok_or_else(|| AsmError {
+                line,
+                message: format!("Unknown instruction '{mnemonic}'"),
+            })?;
+            decoder(&args).map_err(|message| AsmError { line, message })
+        })
+        .collect()
+}
+
+pub fn disassemble(program: &[Box<dyn Instruction>]) -> String {
+    program.iter().map(|i| format!("{i}\n")).collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trace {
+    pub cycle: u64,
+    pub ip: usize,
+    // Register values during the cycle, before the instruction completes
+    pub registers: [i32; REGISTERS.len()],
+    pub instruction: String,
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>6} {:>4}  {:<12}",
+            self.cycle, self.ip, self.instruction
+        )?;
+        for (name, value) in REGISTERS.iter().zip(self.registers) {
+            write!(f, " {name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Cpu {
+    program: Program,
+    machine: Machine,
+    busy: u32,
+    fault: Option<Fault>,
+}
+
+impl Cpu {
+    pub fn new(program: Program) -> Self {
+        Cpu {
+            program,
+            machine: Machine::default(),
+            busy: 0,
+            fault: None,
+        }
+    }
+    pub fn with_register(mut self, register: usize, value: i32) -> Self {
+        self.machine.registers[register] = value;
+        self
+    }
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+    pub fn fault(&self) -> Option<Fault> {
+        self.fault
+    }
+    pub fn is_halted(&self) -> bool {
+        self.machine.ip >= self.program.len() || self.fault.is_some()
+    }
+    // True between instructions, i.e. the next cycle starts a new instruction
+    pub fn at_instruction_start(&self) -> bool {
+        self.busy == 0
+    }
+    // Nothing more runs once the program ends or faults
+    pub fn step(&mut self) -> Option<Trace> {
+        if self.fault.is_some() {
+            return None;
+        }
+        let instruction = self.program.get(self.machine.ip)?;
+        self.machine.cycle += 1;
+        let trace = Trace {
+            cycle: self.machine.cycle,
+            ip: self.machine.ip,
+            registers: self.machine.registers,
+            instruction: instruction.to_string(),
+        };
+        self.busy += 1;
+        if self.busy >= instruction.cycles() {
+            self.busy = 0;
+            let ip = self.machine.ip;
+            self.machine.ip += 1;
+            let result = instruction.execute(&mut self.machine);
+            // Jumping right past the end is a normal way to halt
+            self.fault = match result {
+                Err(fault) => Some(fault),
+                Ok(()) if self.machine.ip > self.program.len() => Some(Fault::JumpOutOfRange {
+                    ip,
+                    target: self.machine.ip as i64,
+                }),
+                Ok(()) => None,
+            };
+        }
+        Some(trace)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Breakpoint {
+    // Stop before running the given cycle
+    Cycle(u64),
+    // Stop before starting the instruction at the given address
+    Address(usize),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Watchpoint {
+    Changed(usize),
+    Equals(usize, i32),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Stop {
+    Halted,
+    Fault(Fault),
+    Breakpoint(Breakpoint),
+    Watchpoint(Watchpoint),
+}
+
+#[derive(Debug)]
+pub struct Debugger {
+    cpu: Cpu,
+    breakpoints: Vec<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    trace: Vec<Trace>,
+}
+
+impl Debugger {
+    pub fn new(cpu: Cpu) -> Self {
+        Debugger {
+            cpu,
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            trace: Vec::new(),
+        }
+    }
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+    pub fn trace(&self) -> &[Trace] {
+        &self.trace
+    }
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
+    pub fn clear(&mut self) {
+        self.breakpoints.clear();
+        self.watchpoints.clear();
+    }
+    pub fn step(&mut self) -> Option<&Trace> {
+        let trace = self.cpu.step()?;
+        self.trace.push(trace);
+        self.trace.last()
+    }
+    // Runs at least one cycle, then until halted or a breakpoint or watchpoint triggers
+    pub fn run(&mut self) -> Stop {
+        loop {
+            let before = self.cpu.machine.registers;
+            if self.step().is_none() {
+                return self.cpu.fault.map_or(Stop::Halted, Stop::Fault);
+            }
+            let after = self.cpu.machine.registers;
+            let watch = self.watchpoints.iter().find(|w| match **w {
+                Watchpoint::Changed(r) => before[r] != after[r],
+                Watchpoint::Equals(r, v) => before[r] != v && after[r] == v,
+            });
+            if let Some(&w) = watch {
+                return Stop::Watchpoint(w);
+            }
+            let next_cycle = self.cpu.machine.cycle + 1;
+            let at_start = self.cpu.at_instruction_start() && !self.cpu.is_halted();
+            let brk = self.breakpoints.iter().find(|b| match **b {
+                Breakpoint::Cycle(c) => c == next_cycle,
+                Breakpoint::Address(a) => at_start && a == self.cpu.machine.ip,
+            });
+            if let Some(&b) = brk {
+                return Stop::Breakpoint(b);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const LOOP: &str = "set y 3 ; counter\nadd x 2\nadd y -1\njnz y -2\nmul x 10\n";
+
+    #[test]
+    fn test_roundtrip() {
+        let program = assemble(LOOP, &InstructionSet::extended()).unwrap();
+        let text = disassemble(&program);
+        assert_eq!(text, "set y 3\nadd x 2\nadd y -1\njnz y -2\nmul x 10\n");
+        let again = assemble(&text, &InstructionSet::extended()).unwrap();
+        assert_eq!(disassemble(&again), text);
+    }
+    #[test]
+    fn test_errors() {
+        let set = InstructionSet::elf();
+        assert_eq!(
+            assemble("noop\nset x 1", &set).unwrap_err(),
+            AsmError {
+                line: 2,
+                message: "Unknown instruction 'set'".into()
+            }
+        );
+        let set = InstructionSet::extended();
+        assert_eq!(assemble("addx", &set).unwrap_err().line, 1);
+        assert_eq!(assemble("add 1 x", &set).unwrap_err().line, 1);
+        assert_eq!(assemble("add x q", &set).unwrap_err().line, 1);
+    }
+    #[test]
+    fn test_loop() {
+        let program = assemble(LOOP, &InstructionSet::extended()).unwrap();
+        let mut cpu = Cpu::new(program);
+        while cpu.step().is_some() {}
+        assert_eq!(cpu.machine().registers, [60, 0, 0, 0]);
+        assert_eq!(cpu.machine().cycle, 1 + 3 * (2 + 2 + 1) + 3);
+    }
+    #[test]
+    fn test_debugger() {
+        let program = assemble(LOOP, &InstructionSet::extended()).unwrap();
+        let mut debugger = Debugger::new(Cpu::new(program));
+        debugger.add_breakpoint(Breakpoint::Address(4));
+        debugger.add_watchpoint(Watchpoint::Equals(0, 4));
+        assert_eq!(debugger.run(), Stop::Watchpoint(Watchpoint::Equals(0, 4)));
+        assert_eq!(debugger.cpu().machine().registers, [4, 2, 0, 0]);
+        assert_eq!(debugger.run(), Stop::Breakpoint(Breakpoint::Address(4)));
+        assert_eq!(debugger.cpu().machine().registers, [6, 0, 0, 0]);
+        debugger.clear();
+        debugger.add_breakpoint(Breakpoint::Cycle(18));
+        assert_eq!(debugger.run(), Stop::Breakpoint(Breakpoint::Cycle(18)));
+        assert_eq!(debugger.cpu().machine().cycle, 17);
+        assert_eq!(debugger.run(), Stop::Halted);
+        assert_eq!(debugger.trace().len(), 19);
+        assert_eq!(
+            debugger.trace()[0].to_string(),
+            "     1    0  set y 3      x=0 y=0 z=0 w=0"
+        );
+    }
+    #[test]
+    fn test_wrapping() {
+        // Squares x until it runs over, which wraps like the hardware would
+        let program = assemble(
+            "set x 3\nset y 6\nmul x x\nadd y -1\njnz y -2\n",
+            &InstructionSet::extended(),
+        )
+        .unwrap();
+        let mut cpu = Cpu::new(program);
+        while cpu.step().is_some() {}
+        assert_eq!(cpu.machine().registers[X], 3i32.wrapping_pow(64));
+        assert_eq!(cpu.fault(), None);
+    }
+    #[test]
+    fn test_faults() {
+        let set = InstructionSet::extended();
+        let mut debugger = Debugger::new(Cpu::new(assemble("noop\njnz 1 -2\nnoop", &set).unwrap()));
+        assert_eq!(
+            debugger.run(),
+            Stop::Fault(Fault::JumpOutOfRange { ip: 1, target: -1 })
+        );
+        assert!(debugger.cpu().is_halted());
+        assert!(debugger.step().is_none());
+        let mut debugger = Debugger::new(Cpu::new(assemble("jnz 1 5\nnoop", &set).unwrap()));
+        assert_eq!(
+            debugger.run(),
+            Stop::Fault(Fault::JumpOutOfRange { ip: 0, target: 5 })
+        );
+        // Landing right after the last instruction just halts
+        let mut debugger = Debugger::new(Cpu::new(assemble("jnz 1 2\nnoop", &set).unwrap()));
+        assert_eq!(debugger.run(), Stop::Halted);
+        assert_eq!(debugger.cpu().machine().ip, 2);
+    }
+}
@@ -1,66 +1,24 @@
 use itertools::Itertools;
-use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    character::complete as ncc,
-    combinator::{map, value},
-    sequence::preceded,
-};
-
-#[derive(Copy, Clone)]
-enum Op {
-    Noop,
-    AddX(i32),
-}
+use day10::cpu::{self, Cpu, InstructionSet};
+use day10::ocr;
 
 #[derive(Default, Clone)]
 struct State {
     x: i32,
     pc: i32,
 }
-impl State {
-    fn new(x: i32, pc: i32) -> Self {
-        Self { x, pc }
-    }
-    fn inc_pc(&self, dpc: i32) -> State {
-        Self {
-            x: self.x,
-            pc: self.pc + dpc,
-        }
-    }
-}
 
-fn parse<'a>(input: &'a str) -> impl Iterator<Item=Op> + 'a {
-    fn op(input: &str) -> nom::IResult<&str, Op> {
-        alt((value(Op::Noop, tag("noop")), map(preceded(tag("addx "), ncc::i32), |val| Op::AddX(val))))(input)
-    }
-    fn newline(input: &str) -> nom::IResult<&str, &str> {
-        ncc::line_ending(input)
-    }
-    (0..).scan(input, |input, _| {
-        let (rest, o) = op(input).ok()?;
-        *input = newline(rest).ok()?.0;
-        Some(o)
+fn execute(input: &str) -> impl Iterator<Item = State> {
+    let program = cpu::assemble(input, &InstructionSet::elf()).expect("Invalid program");
+    let mut vm = Cpu::new(program).with_register(cpu::X, 1);
+    std::iter::from_fn(move || vm.step()).map(|t| State {
+        x: t.registers[cpu::X],
+        pc: t.cycle as i32,
     })
 }
 
-fn execute<'a>(program: impl Iterator<Item=Op> + 'a) -> impl Iterator<Item = State> + 'a {
-    program
-        .scan(State::new(1, 0), |state, op| {
-            match op {
-                Op::Noop => (std::mem::replace(state, state.inc_pc(1)), 1),
-                Op::AddX(dx) => (
-                    std::mem::replace(state, State::new(state.x + dx, state.pc + 2)),
-                    2,
-                ),
-            }
-            .into()
-        })
-        .flat_map(move |(s, n)| (1..=n).map(move |dpc| s.inc_pc(dpc)))
-}
-
 fn part1(input: &str) -> i32 {
-    execute(parse(input))
+    execute(input)
         .skip(19)
         .step_by(40)
         .map(|s| s.pc * s.x)
@@ -74,7 +32,7 @@ fn part2(input: &str) -> String {
             .then_some('#')
             .unwrap_or('.')
     };
-    execute(parse(input))
+    execute(input)
         .chunks(LINE_WIDTH as usize)
         .into_iter()
         .map(|chunk| chunk.map(pixel).collect::<String>())
@@ -83,8 +41,21 @@ fn part2(input: &str) -> String {
 
 fn main() {
     let input = include_str!("../puzzle_input.txt");
+    if std::env::args().nth(1).as_deref() == Some("trace") {
+        let program = cpu::assemble(input, &InstructionSet::elf()).expect("Invalid program");
+        let mut vm = Cpu::new(program).with_register(cpu::X, 1);
+        while let Some(trace) = vm.step() {
+            println!("{trace}");
+        }
+        return;
+    }
     println!("Part 1: {}", part1(input));
-    println!("Part 2:\n{}", part2(input));
+    let image = part2(input);
+    match ocr::decode(image.lines()) {
+        Ok(text) => println!("Part 2: {text}"),
+        Err(e) => println!("Part 2: {e}"),
+    }
+    println!("{image}");
 }
 
 #[cfg(test)]
@@ -101,4 +72,9 @@ mod test {
             include_str!("../part2_test_output.txt").trim()
         )
     }
+    #[test]
+    fn test_part2_puzzle() {
+        let image = part2(include_str!("../puzzle_input.txt"));
+        assert_eq!(ocr::decode(image.lines()).as_deref(), Ok("PLPAFBCL"));
+    }
 }
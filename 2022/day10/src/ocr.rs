@@ -0,0 +1,165 @@
+use std::fmt;
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+const GLYPH_SPACING: usize = 1;
+
+// Block letters seen in puzzle outputs, rows concatenated top to bottom
+const ALPHABET: [(char, &str); 18] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Y', "#...#....#.#..#...#...#."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownGlyph {
+    pub index: usize,
+    pub column: usize,
+    pub pattern: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OcrError {
+    Height(usize),
+    RowWidth { row: usize, width: usize },
+    UnknownGlyphs(Vec<UnknownGlyph>),
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::Height(h) => write!(f, "Expected {GLYPH_HEIGHT} rows, got {h}"),
+            OcrError::RowWidth { row, width } => {
+                write!(f, "Row {row} has mismatched width {width}")
+            }
+            OcrError::UnknownGlyphs(glyphs) => {
+                write!(f, "Unknown glyphs:")?;
+                for g in glyphs {
+                    write!(f, "\n  #{} at column {}:", g.index, g.column)?;
+                    for row in g.pattern.as_bytes().chunks(GLYPH_WIDTH) {
+                        write!(f, "\n    {}", std::str::from_utf8(row).unwrap())?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn recognize(pattern: &str) -> Option<char> {
+    ALPHABET
+        .iter()
+        .find(|(_, glyph)| *glyph == pattern)
+        .map(|(c, _)| *c)
+}
+
+// Reads rows of `#` and `.` pixels, any other lit character is treated as `#`
+pub fn decode<S: AsRef<str>>(rows: impl IntoIterator<Item = S>) -> Result<String, OcrError> {
+    let rows: Vec<Vec<bool>> = rows
+        .into_iter()
+        .map(|row| {
+            row.as_ref()
+                .trim_end()
+                .chars()
+                .map(|c| c != '.' && c != ' ')
+                .collect()
+        })
+        .filter(|row: &Vec<bool>| !row.is_empty())
+        .collect();
+    if rows.len() != GLYPH_HEIGHT {
+        return Err(OcrError::Height(rows.len()));
+    }
+    let width = rows[0].len();
+    if let Some(row) = rows.iter().position(|r| r.len() != width) {
+        return Err(OcrError::RowWidth {
+            row,
+            width: rows[row].len(),
+        });
+    }
+
+    let stride = GLYPH_WIDTH + GLYPH_SPACING;
+    let mut text = String::new();
+    let mut unknown = Vec::new();
+    for (index, column) in (0..width).step_by(stride).enumerate() {
+        let pattern: String = rows
+            .iter()
+            .flat_map(|row| {
+                (column..column + GLYPH_WIDTH).map(|x| row.get(x).copied().unwrap_or(false))
+            })
+            .map(|lit| if lit { '#' } else { '.' })
+            .collect();
+        match recognize(&pattern) {
+            Some(c) => text.push(c),
+            None => unknown.push(UnknownGlyph {
+                index,
+                column,
+                pattern,
+            }),
+        }
+    }
+    if unknown.is_empty() {
+        Ok(text)
+    } else {
+        Err(OcrError::UnknownGlyphs(unknown))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn render(text: &str) -> Vec<String> {
+        (0..GLYPH_HEIGHT)
+            .map(|y| {
+                text.chars()
+                    .map(|c| {
+                        let (_, glyph) = ALPHABET.iter().find(|(g, _)| *g == c).unwrap();
+                        &glyph[y * GLYPH_WIDTH..(y + 1) * GLYPH_WIDTH]
+                    })
+                    .collect::<Vec<_>>()
+                    .join(".")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_alphabet_roundtrip() {
+        let all: String = ALPHABET.iter().map(|(c, _)| *c).collect();
+        assert_eq!(decode(render(&all)), Ok(all));
+    }
+    #[test]
+    fn test_unknown() {
+        let mut rows = render("ABC");
+        rows[2].replace_range(5..6, ".");
+        assert_eq!(
+            decode(&rows),
+            Err(OcrError::UnknownGlyphs(vec![UnknownGlyph {
+                index: 1,
+                column: 5,
+                pattern: "###.#..#.##.#..##..####.".into()
+            }]))
+        );
+    }
+    #[test]
+    fn test_bad_dimensions() {
+        assert_eq!(decode(["####"]), Err(OcrError::Height(1)));
+        let mut rows = render("AB");
+        rows[3].push('#');
+        assert_eq!(decode(&rows), Err(OcrError::RowWidth { row: 3, width: 10 }));
+    }
+}
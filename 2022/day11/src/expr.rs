@@ -0,0 +1,234 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete as ncc,
+    combinator::{map, value},
+    multi::fold_many0,
+    sequence::{delimited, pair},
+};
+use num::{BigInt, Integer, Zero};
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Const(i128),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+pub trait Worry: Clone {
+    fn from_i128(value: i128) -> Self;
+    fn apply(&self, op: BinOp, rhs: &Self) -> Self;
+    fn rem(&self, modulus: i128) -> Self;
+    fn divisible_by(&self, divisor: i128) -> bool;
+}
+
+impl Worry for i128 {
+    fn from_i128(value: i128) -> Self {
+        value
+    }
+    fn apply(&self, op: BinOp, rhs: &Self) -> Self {
+        if op == BinOp::Div && *rhs == 0 {
+            panic!("Worry level divided by zero");
+        }
+        match op {
+            BinOp::Add => self.checked_add(*rhs),
+            BinOp::Sub => self.checked_sub(*rhs),
+            BinOp::Mul => self.checked_mul(*rhs),
+            BinOp::Div => self.checked_div(*rhs),
+        }
+        .expect("Worry level overflow")
+    }
+    fn rem(&self, modulus: i128) -> Self {
+        self.rem_euclid(modulus)
+    }
+    fn divisible_by(&self, divisor: i128) -> bool {
+        self % divisor == 0
+    }
+}
+
+impl Worry for BigInt {
+    fn from_i128(value: i128) -> Self {
+        value.into()
+    }
+    fn apply(&self, op: BinOp, rhs: &Self) -> Self {
+        if op == BinOp::Div && rhs.is_zero() {
+            panic!("Worry level divided by zero");
+        }
+        match op {
+            BinOp::Add => self + rhs,
+            BinOp::Sub => self - rhs,
+            BinOp::Mul => self * rhs,
+            BinOp::Div => self / rhs,
+        }
+    }
+    fn rem(&self, modulus: i128) -> Self {
+        self.mod_floor(&modulus.into())
+    }
+    fn divisible_by(&self, divisor: i128) -> bool {
+        (self % BigInt::from(divisor)).is_zero()
+    }
+}
+
+impl Expr {
+    pub fn eval<W: Worry>(&self, old: &W) -> W {
+        match self {
+            Expr::Old => old.clone(),
+            Expr::Const(c) => W::from_i128(*c),
+            Expr::Binary(op, lhs, rhs) => lhs.eval(old).apply(*op, &rhs.eval(old)),
+        }
+    }
+    // Same modulo `modulus`, reducing after every operation so nothing grows past it.
+    // Only valid without division.
+    pub fn eval_mod<W: Worry>(&self, old: &W, modulus: i128) -> W {
+        match self {
+            Expr::Old => old.rem(modulus),
+            Expr::Const(c) => W::from_i128(*c).rem(modulus),
+            Expr::Binary(op, lhs, rhs) => {
+                let (a, b) = (lhs.eval_mod(old, modulus), rhs.eval_mod(old, modulus));
+                a.apply(*op, &b).rem(modulus)
+            }
+        }
+    }
+    pub fn has_division(&self) -> bool {
+        match self {
+            Expr::Old | Expr::Const(_) => false,
+            Expr::Binary(op, lhs, rhs) => {
+                *op == BinOp::Div || lhs.has_division() || rhs.has_division()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Const(c) => write!(f, "{c}"),
+            Expr::Binary(op, lhs, rhs) => {
+                let op = match op {
+                    BinOp::Add => '+',
+                    BinOp::Sub => '-',
+                    BinOp::Mul => '*',
+                    BinOp::Div => '/',
+                };
+                write!(f, "({lhs} {op} {rhs})")
+            }
+        }
+    }
+}
+
+fn ws<'a, O>(
+    inner: impl FnMut(&'a str) -> nom::IResult<&'a str, O>,
+) -> impl FnMut(&'a str) -> nom::IResult<&'a str, O> {
+    delimited(ncc::space0, inner, ncc::space0)
+}
+
+fn atom(input: &str) -> nom::IResult<&str, Expr> {
+    ws(alt((
+        value(Expr::Old, tag("old")),
+        map(ncc::i128, Expr::Const),
+        delimited(ncc::char('('), expression, ncc::char(')')),
+    )))(input)
+}
+
+fn term(input: &str) -> nom::IResult<&str, Expr> {
+    let (input, first) = atom(input)?;
+    let operator = alt((
+        value(BinOp::Mul, ncc::char('*')),
+        value(BinOp::Div, ncc::char('/')),
+    ));
+    fold_many0(
+        pair(ws(operator), atom),
+        move || first.clone(),
+        |lhs, (op, rhs)| Expr::Binary(op, lhs.into(), rhs.into()),
+    )(input)
+}
+
+pub fn expression(input: &str) -> nom::IResult<&str, Expr> {
+    let (input, first) = term(input)?;
+    let operator = alt((
+        value(BinOp::Add, ncc::char('+')),
+        value(BinOp::Sub, ncc::char('-')),
+    ));
+    fold_many0(
+        pair(ws(operator), term),
+        move || first.clone(),
+        |lhs, (op, rhs)| Expr::Binary(op, lhs.into(), rhs.into()),
+    )(input)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nom::Finish;
+
+    fn parse(s: &str) -> Expr {
+        nom::combinator::all_consuming(expression)(s)
+            .finish()
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(parse("old * old").to_string(), "(old * old)");
+        assert_eq!(
+            parse("1 + 2 * old - 3").to_string(),
+            "((1 + (2 * old)) - 3)"
+        );
+        assert_eq!(
+            parse("(1 + 2) * (old - 3) / 2").to_string(),
+            "(((1 + 2) * (old - 3)) / 2)"
+        );
+        assert_eq!(parse("old - -2").eval(&5i128), 7);
+    }
+    #[test]
+    fn test_eval() {
+        let e = parse("(old + 3) * old / 2");
+        assert_eq!(e.eval(&7i128), 35);
+        assert_eq!(e.eval(&BigInt::from(7)), BigInt::from(35));
+        assert!(e.has_division());
+        assert!(!parse("old * 19 + old").has_division());
+    }
+    #[test]
+    fn test_bigint() {
+        let big = parse("old * old").eval(&BigInt::from(i128::MAX));
+        assert!(big > BigInt::from(i128::MAX));
+        assert!(big.divisible_by(7) == (i128::MAX % 7 == 0));
+    }
+    #[test]
+    #[should_panic(expected = "Worry level divided by zero")]
+    fn test_division_by_zero() {
+        parse("old / 0").eval(&5i128);
+    }
+    #[test]
+    #[should_panic(expected = "Worry level divided by zero")]
+    fn test_bigint_division_by_zero() {
+        parse("old / (old - old)").eval(&BigInt::from(5));
+    }
+    #[test]
+    #[should_panic(expected = "Worry level overflow")]
+    fn test_overflow() {
+        parse("old * old").eval(&i128::MAX);
+    }
+    #[test]
+    fn test_eval_mod() {
+        // The cube doesn't fit in an i128, but every step modulo m does
+        let e = parse("old * old * old - 5 * old");
+        let m = 1_000_000_000_000_037;
+        let old = 999_999_999_999_999i128;
+        let exact = e.eval(&BigInt::from(old)).rem(m);
+        assert_eq!(BigInt::from(e.eval_mod(&old, m)), exact);
+        assert_eq!(e.eval_mod(&BigInt::from(old), m), exact);
+        assert_eq!(parse("old - 10").eval_mod(&3i128, 7), 0);
+    }
+}
@@ -1,35 +1,29 @@
-use std::collections::VecDeque;
-
+use expr::{expression, BinOp, Expr, Worry};
 use itertools::Itertools;
 use nom::{
-    branch::alt,
     bytes::complete::tag,
     character::complete as ncc,
-    combinator::{map, into},
+    combinator::into,
     sequence::{preceded, delimited, tuple, terminated}, multi::separated_list0, Finish,
 };
+use num::{BigInt, Integer};
 
-#[derive(Debug)]
-enum Operation {
-    Add(i128),
-    Mul(i128),
-    Square
-}
-type Items = VecDeque<i128>;
+mod expr;
+
+type Items = Vec<i128>;
 
 #[derive(Debug)]
 struct Monkey {
     items: Items,
-    operation: Operation,
+    operation: Expr,
     test: i128,
-    if_true: u32,
-    if_false: u32,
-    inspected: u128
+    if_true: usize,
+    if_false: usize,
 }
 
-impl From<(Vec<i128>, Operation, i128, u32, u32)> for Monkey {
-    fn from((items, operation, test, if_true, if_false): (Vec<i128>, Operation, i128, u32, u32)) -> Self {
-        Self { items: items.into(), operation, test, if_true, if_false, inspected: 0 }
+impl From<(Items, Expr, i128, u32, u32)> for Monkey {
+    fn from((items, operation, test, if_true, if_false): (Items, Expr, i128, u32, u32)) -> Self {
+        Self { items, operation, test, if_true: if_true as usize, if_false: if_false as usize }
     }
 }
 
@@ -38,11 +32,7 @@ fn parse(input: &str) -> nom::IResult<&str, Vec<Monkey>> {
         terminated(delimited(tag("Monkey "), ncc::u32, tag(":")), ncc::line_ending)(input)
     }
     let items = delimited(tag("  Starting items: "), separated_list0(tag(", "), ncc::i128), ncc::line_ending);
-    let operation = delimited(tag("  Operation: new = old "), alt((
-                map(tag("* old"), |_| Operation::Square),
-                map(preceded(tag("+ "), ncc::i128), |x| Operation::Add(x)),
-                map(preceded(tag("* "), ncc::i128), |x| Operation::Mul(x))
-                )), ncc::line_ending);
+    let operation = delimited(tag("  Operation: new = "), expression, ncc::line_ending);
     let test = delimited(tag("  Test: divisible by "), ncc::i128, ncc::line_ending);
     let if_true = delimited(tag("    If true: throw to monkey "), ncc::u32, ncc::line_ending);
     let if_false = delimited(tag("    If false: throw to monkey "), ncc::u32, ncc::line_ending);
@@ -50,41 +40,55 @@ fn parse(input: &str) -> nom::IResult<&str, Vec<Monkey>> {
     separated_list0(ncc::line_ending, monkey)(input)
 }
 
-fn monkey_business(mut monkeys: Vec<Monkey>, iterations: usize, worry_reduction: impl Fn(i128) -> i128) -> u128 {
-    for _ in 0..iterations {
-        for i in 0..monkeys.len() {
-            let mut items = std::mem::take(&mut monkeys[i].items);
-            for item in items.drain(0..) {
-                let monkey = &mut monkeys[i];
-                monkey.inspected += 1;
-                let worry = worry_reduction(match monkey.operation {
-                    Operation::Add(x) => item + x,
-                    Operation::Mul(x) => item * x,
-                    Operation::Square => item * item
-                });
-
-                let next = (worry % monkey.test == 0)
-                    .then_some(monkey.if_true)
-                    .unwrap_or(monkey.if_false);
+// Worry levels can be kept modulo the divisibility tests only when nothing divides them
+fn modulus(monkeys: &[Monkey], relief: Option<i128>) -> Option<i128> {
+    if relief.is_some() || monkeys.iter().any(|m| m.operation.has_division()) {
+        None
+    } else {
+        Some(monkeys.iter().fold(1, |acc, m| acc.lcm(&m.test)))
+    }
+}
 
-                monkeys[next as usize].items.push_back(worry);
+fn inspections<W: Worry>(monkeys: &[Monkey], rounds: usize, relief: Option<i128>, modulus: Option<i128>) -> Vec<u128> {
+    let mut items: Vec<Vec<W>> = monkeys.iter()
+        .map(|m| m.items.iter().map(|&i| W::from_i128(i)).collect())
+        .collect();
+    let mut inspected = vec![0; monkeys.len()];
+    for _ in 0..rounds {
+        for (i, monkey) in monkeys.iter().enumerate() {
+            for item in std::mem::take(&mut items[i]) {
+                inspected[i] += 1;
+                let mut worry = match modulus {
+                    Some(modulus) => monkey.operation.eval_mod(&item, modulus),
+                    None => monkey.operation.eval(&item),
+                };
+                if let Some(relief) = relief {
+                    worry = worry.apply(BinOp::Div, &W::from_i128(relief));
+                }
+                let next = if worry.divisible_by(monkey.test) { monkey.if_true } else { monkey.if_false };
+                items[next].push(worry);
             }
-            monkeys[i].items = items;
         }
     }
+    inspected
+}
 
-    monkeys.into_iter().map(|m| m.inspected).sorted().rev().take(2).product()
+fn monkey_business(monkeys: &[Monkey], rounds: usize, relief: Option<i128>) -> u128 {
+    let inspected = match modulus(monkeys, relief) {
+        Some(m) => inspections::<i128>(monkeys, rounds, relief, Some(m)),
+        None => inspections::<BigInt>(monkeys, rounds, relief, None),
+    };
+    inspected.into_iter().sorted().rev().take(2).product()
 }
 
 fn part1(input: &str) -> u128 {
     let (_, monkeys) = parse(input).finish().expect("Parse error");
-    monkey_business(monkeys, 20, |x| x / 3)
+    monkey_business(&monkeys, 20, Some(3))
 }
 
 fn part2(input: &str) -> u128 {
     let (_, monkeys) = parse(input).finish().expect("Parse error");
-    let div: i128 = monkeys.iter().map(|m| m.test).product();
-    monkey_business(monkeys , 10_000, move |x| x % div)
+    monkey_business(&monkeys, 10_000, None)
 }
 
 fn main() {
@@ -104,4 +108,27 @@ mod test {
     fn test_part2() {
         assert_eq!(part2(include_str!("../test_input.txt")), 2713310158);
     }
+    #[test]
+    fn test_modulus() {
+        let (_, monkeys) = parse(include_str!("../test_input.txt")).finish().unwrap();
+        assert_eq!(modulus(&monkeys, None), Some(23 * 19 * 13 * 17));
+        assert_eq!(modulus(&monkeys, Some(3)), None);
+    }
+    #[test]
+    fn test_unreduced_matches_modular() {
+        let (_, monkeys) = parse(include_str!("../test_input.txt")).finish().unwrap();
+        assert_eq!(
+            inspections::<BigInt>(&monkeys, 200, None, None),
+            inspections::<i128>(&monkeys, 200, None, modulus(&monkeys, None))
+        );
+    }
+    #[test]
+    fn test_expression_operations() {
+        let input = include_str!("../test_input.txt")
+            .replace("new = old * 19", "new = (old - 1) * 19 + 19")
+            .replace("new = old + 6", "new = 2 * (old + 3) / 2 + 3");
+        let (_, monkeys) = parse(&input).finish().unwrap();
+        assert!(modulus(&monkeys, None).is_none());
+        assert_eq!(monkey_business(&monkeys, 20, Some(3)), 10605);
+    }
 }
@@ -1,13 +1,15 @@
-use std::collections::{HashMap, VecDeque};
-
 use nom::{
     IResult, Finish,
     branch::alt,
     character::complete as ncc,
     combinator::{map, value},
-    multi::{separated_list0, many0}
+    multi::{separated_list0, many1}
 };
 
+use route::{Climb, DistanceField};
+
+mod route;
+
 #[derive(Copy, Clone, Debug)]
 enum Node {
     Start(u32),
@@ -29,13 +31,13 @@ impl Node {
 
 fn parse(input: &str) -> IResult<&str, Vec<Vec<Node>>> {
     let node = alt((
-            map(ncc::satisfy(|ch| ('a'..='z').contains(&ch)), |ch| Node::Path(ch as u32 - 'a' as u32)),
-            value(Node::Start('a' as u32 - 'a' as u32), ncc::char('S')),
+            map(ncc::satisfy(|ch| ch.is_ascii_lowercase()), |ch| Node::Path(ch as u32 - 'a' as u32)),
+            value(Node::Start(0), ncc::char('S')),
             value(Node::End('z' as u32 - 'a' as u32), ncc::char('E'))));
-    separated_list0(ncc::line_ending, many0(node))(input)
+    separated_list0(ncc::line_ending, many1(node))(input)
 }
 
-fn nodes<'a>(map: &'a Map) -> impl Iterator<Item=(&'a Node, Position)> {
+fn nodes(map: &Map) -> impl Iterator<Item=(&Node, Position)> {
     map
         .iter()
         .enumerate()
@@ -59,59 +61,38 @@ fn find_start_and_end(map: &Map) -> Option<(Position, Position)> {
     }
 }
 
-fn neighbors<'a>(map: &'a Map, (x, y): Position) -> impl Iterator<Item=Position> + 'a {
-    let height = map[y][x].height();
-    let (x, y) = (x as i32, y as i32);
-    [(x-1, y), (x+1, y), (x, y-1), (x, y+1)]
-        .into_iter()
-        .filter_map(|(x, y)| Some((usize::try_from(x).ok()?, usize::try_from(y).ok()?)))
-        .filter_map(|(x, y)| Some((map.get(y)?.get(x)?, (x, y))))
-        .filter_map(move |(n, pos)| (n.height() <= height + 1).then_some(pos)) 
-}
-
-fn shortest_path<'a>(map: &Map, starts: &'a [Position], end: Position) -> Option<impl Iterator<Item=Position> + 'a> {
-    let num_nodes: usize = map.iter().map(Vec::len).sum::<usize>();
-    let mut queue = VecDeque::with_capacity(num_nodes/10);
-    queue.extend(starts.iter().copied());
-    let mut parents = HashMap::with_capacity(num_nodes/4);
-
-    while let Some(node) = queue.pop_front() {
-        if node == end {
-            return (0..).scan(node, move |pos, _| {
-                let node = *pos;
-                *pos = *parents.get(pos)?;
-                (!starts.contains(&node)).then_some(*pos)
-            })
-            .into()
-        }
-        for n in neighbors(map, node) {
-            parents.entry(n).or_insert_with(|| {
-                queue.push_back(n);
-                node
-            });
-        }
-    }
-    None
-}
 fn part1(input: &str) -> usize {
     let (_, map) = parse(input).finish().expect("Parse error");
     let (start, end) = find_start_and_end(&map).expect("No start or end");
-    shortest_path(&map, &[start], end).expect("No path").count()
+    let field = DistanceField::new(&map, end, Climb::default());
+    field.distance(start).expect("No path")
 }
 
 fn part2(input: &str) -> usize {
     let (_, map) = parse(input).finish().expect("Parse error");
     let (_, end) = find_start_and_end(&map).expect("No start or end");
-    let starts: Vec<_> = nodes(&map)
-        .filter_map(|(n, pos)| (n.height() == 0).then_some(pos))
-        .collect();
-    shortest_path(&map, &starts, end).expect("No path").count()
+    let field = DistanceField::new(&map, end, Climb::default());
+    field.nearest(0).expect("No path").1
 }
 
 fn main() {
     let input = include_str!("../puzzle_input.txt");
     println!("Part 1: {}", part1(input));
     println!("Part 2: {}", part2(input));
+    if std::env::args().nth(1).as_deref() == Some("route") {
+        let step = |n: Option<String>, default| n.map_or(default, |s| s.parse().expect("Invalid step"));
+        let climb = Climb {
+            up: step(std::env::args().nth(2), 1),
+            down: step(std::env::args().nth(3), u32::MAX)
+        };
+        let (_, map) = parse(input).finish().expect("Parse error");
+        let (start, end) = find_start_and_end(&map).expect("No start or end");
+        let field = DistanceField::new(&map, end, climb);
+        match field.route(start) {
+            Some(route) => println!("{}\n{} steps", field.render(&route), route.len() - 1),
+            None => println!("No route with {climb:?}")
+        }
+    }
 }
 
 #[cfg(test)]
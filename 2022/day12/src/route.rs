@@ -0,0 +1,176 @@
+use std::collections::VecDeque;
+
+use crate::{nodes, Map, Position};
+
+// Largest steps allowed between neighbouring cells
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Climb {
+    pub up: u32,
+    pub down: u32,
+}
+
+impl Default for Climb {
+    // Rules from the puzzle, one step up and any amount down
+    fn default() -> Self {
+        Climb {
+            up: 1,
+            down: u32::MAX,
+        }
+    }
+}
+
+impl Climb {
+    fn allows(&self, map: &Map, (fx, fy): Position, (tx, ty): Position) -> bool {
+        let (from, to) = (map[fy][fx].height(), map[ty][tx].height());
+        to <= from.saturating_add(self.up) && from <= to.saturating_add(self.down)
+    }
+}
+
+fn adjacent(map: &Map, (x, y): Position) -> impl Iterator<Item = Position> + '_ {
+    [
+        (x.wrapping_sub(1), y),
+        (x + 1, y),
+        (x, y.wrapping_sub(1)),
+        (x, y + 1),
+    ]
+    .into_iter()
+    .filter(|(x, y)| map.get(*y).is_some_and(|row| *x < row.len()))
+}
+
+// Steps from every cell to the end, found with a single BFS walking backwards from it
+pub struct DistanceField<'a> {
+    map: &'a Map,
+    climb: Climb,
+    end: Position,
+    distances: Vec<Vec<Option<usize>>>,
+}
+
+impl<'a> DistanceField<'a> {
+    pub fn new(map: &'a Map, end: Position, climb: Climb) -> Self {
+        let mut distances: Vec<Vec<Option<usize>>> =
+            map.iter().map(|row| vec![None; row.len()]).collect();
+        distances[end.1][end.0] = Some(0);
+        let mut queue = VecDeque::from([end]);
+        while let Some(pos) = queue.pop_front() {
+            let d = distances[pos.1][pos.0].unwrap();
+            for prev in adjacent(map, pos) {
+                if distances[prev.1][prev.0].is_none() && climb.allows(map, prev, pos) {
+                    distances[prev.1][prev.0] = Some(d + 1);
+                    queue.push_back(prev);
+                }
+            }
+        }
+        DistanceField {
+            map,
+            climb,
+            end,
+            distances,
+        }
+    }
+
+    pub fn distance(&self, (x, y): Position) -> Option<usize> {
+        *self.distances.get(y)?.get(x)?
+    }
+
+    // Closest cell to the end of the given height
+    pub fn nearest(&self, height: u32) -> Option<(Position, usize)> {
+        nodes(self.map)
+            .filter(|(n, _)| n.height() == height)
+            .filter_map(|(_, pos)| Some((pos, self.distance(pos)?)))
+            .min_by_key(|(_, d)| *d)
+    }
+
+    // A shortest route from `start`, including both ends
+    pub fn route(&self, start: Position) -> Option<Vec<Position>> {
+        let mut pos = start;
+        let mut d = self.distance(start)?;
+        let mut route = vec![start];
+        while d > 0 {
+            pos = adjacent(self.map, pos)
+                .find(|n| self.distance(*n) == Some(d - 1) && self.climb.allows(self.map, pos, *n))
+                .unwrap();
+            d -= 1;
+            route.push(pos);
+        }
+        Some(route)
+    }
+
+    // Heightmap letters with the route drawn over it as arrows
+    pub fn render(&self, route: &[Position]) -> String {
+        let mut grid: Vec<Vec<char>> = self
+            .map
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|n| char::from(b'a' + n.height() as u8))
+                    .collect()
+            })
+            .collect();
+        for w in route.windows(2) {
+            let ((x0, y0), (x1, y1)) = (w[0], w[1]);
+            grid[y0][x0] = match (x1 as isize - x0 as isize, y1 as isize - y0 as isize) {
+                (1, 0) => '>',
+                (-1, 0) => '<',
+                (0, 1) => 'v',
+                _ => '^',
+            };
+        }
+        grid[self.end.1][self.end.0] = 'E';
+        grid.into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{find_start_and_end, parse};
+    use nom::Finish;
+
+    fn map() -> Map {
+        parse(include_str!("../test_input.txt"))
+            .finish()
+            .expect("Parse error")
+            .1
+    }
+
+    #[test]
+    fn test_distance_field() {
+        let map = map();
+        let (start, end) = find_start_and_end(&map).unwrap();
+        let field = DistanceField::new(&map, end, Climb::default());
+        assert_eq!(field.distance(start), Some(31));
+        assert_eq!(field.distance(end), Some(0));
+        assert_eq!(field.nearest(0), Some(((0, 4), 29)));
+        assert_eq!(field.distance((9, 9)), None);
+    }
+    #[test]
+    fn test_render() {
+        let map = map();
+        let (start, end) = find_start_and_end(&map).unwrap();
+        let field = DistanceField::new(&map, end, Climb::default());
+        let route = field.route(start).unwrap();
+        assert_eq!(route.len(), 32);
+        assert!(route
+            .windows(2)
+            .all(|w| w[0].0.abs_diff(w[1].0) + w[0].1.abs_diff(w[1].1) == 1));
+        assert_eq!(
+            field.render(&route),
+            [">>vv<<<<", "abvvv<<^", "acvv>E^^", "acv>>>^^", "ab>>>>>^"].join("\n")
+        );
+    }
+    #[test]
+    fn test_climb_rules() {
+        let map = map();
+        let (start, end) = find_start_and_end(&map).unwrap();
+        // The route never goes down, so limiting that doesn't change it
+        let strict = DistanceField::new(&map, end, Climb { up: 1, down: 1 });
+        assert_eq!(strict.distance(start), Some(31));
+        let flat = DistanceField::new(&map, end, Climb { up: 0, down: 0 });
+        assert_eq!(flat.distance(start), None);
+        let free = DistanceField::new(&map, end, Climb { up: 25, down: 25 });
+        assert_eq!(free.distance(start), Some(7));
+    }
+}
@@ -1,50 +1,19 @@
-use std::cmp::Ordering;
-
 use nom::{
-    branch::alt,
     character::complete as ncc,
-    combinator::map,
     multi::separated_list0,
-    sequence::{delimited, terminated, tuple},
+    sequence::{terminated, tuple},
     Finish,
 };
 
-#[derive(Debug, PartialEq, Eq, Ord, Clone)]
-enum Value {
-    Literal(i32),
-    List(Vec<Value>),
-}
-type Pairs = Vec<(Value, Value)>;
+use packet::{sorted_positions, value, Value};
 
-const PUZZLE_INPUT: &str = include_str!("../puzzle_input.txt");
+mod packet;
 
-impl PartialOrd for Value {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        fn cmp_lists(l: &[Value], r: &[Value]) -> Option<Ordering> {
-            l.iter()
-                .zip(r.iter())
-                .filter_map(|(li, ri)| li.partial_cmp(ri))
-                .filter(|o| o.is_ne())
-                .next()
-                .or(Some(l.len().cmp(&r.len())))
-        }
+type Pairs = Vec<(Value, Value)>;
 
-        use Value::*;
-        match (self, other) {
-            (Literal(l), Literal(r)) => l.partial_cmp(r),
-            (List(l), List(r)) => cmp_lists(l, r),
-            (Literal(l), List(r)) => cmp_lists(&[Literal(*l)], r),
-            (List(l), Literal(r)) => cmp_lists(l, &[Literal(*r)]),
-        }
-    }
-}
+const PUZZLE_INPUT: &str = include_str!("../puzzle_input.txt");
 
 fn parse(input: &str) -> nom::IResult<&str, Pairs> {
-    fn value(i: &str) -> nom::IResult<&str, Value> {
-        let values = separated_list0(ncc::char(','), value);
-        let value_list = delimited(ncc::char('['), values, ncc::char(']'));
-        alt((map(ncc::i32, Value::Literal), map(value_list, Value::List)))(i)
-    }
     let packet = |i| terminated(value, ncc::line_ending)(i);
     separated_list0(ncc::line_ending, tuple((packet, packet)))(input)
 }
@@ -53,29 +22,14 @@ fn part1(pairs: &Pairs) -> usize {
     pairs
         .iter()
         .enumerate()
-        .filter_map(|(i, (l, r))| Some((i, l.partial_cmp(r)?)))
-        .filter_map(|(i, o)| (o.is_le()).then_some(i + 1))
+        .filter_map(|(i, (l, r))| l.compare(r).is_le().then_some(i + 1))
         .sum()
 }
 
 fn part2(pairs: Pairs) -> usize {
-    let filters = [
-        Value::List(vec![Value::List(vec![Value::Literal(2)])]),
-        Value::List(vec![Value::List(vec![Value::Literal(6)])]),
-    ];
-    let mut values: Vec<_> = pairs
-        .into_iter()
-        .flat_map(|(l, r)| [l, r].into_iter())
-        .chain(filters.iter().cloned())
-        .collect();
-
-    values.sort();
-
-    values
-        .into_iter()
-        .enumerate()
-        .filter_map(|(i, v)| filters.contains(&v).then_some(i + 1))
-        .product()
+    let dividers = ["[[2]]".parse().unwrap(), "[[6]]".parse().unwrap()];
+    let packets: Vec<Value> = pairs.into_iter().flat_map(|(l, r)| [l, r]).collect();
+    sorted_positions(&packets, &dividers).into_iter().product()
 }
 
 fn main() {
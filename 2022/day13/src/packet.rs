@@ -0,0 +1,215 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use nom::{
+    branch::alt,
+    character::complete as ncc,
+    combinator::{all_consuming, map},
+    multi::separated_list0,
+    sequence::delimited,
+    Finish,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum Value {
+    Literal(i32),
+    List(Vec<Value>),
+}
+
+pub fn value(i: &str) -> nom::IResult<&str, Value> {
+    let values = separated_list0(ncc::char(','), value);
+    let value_list = delimited(ncc::char('['), values, ncc::char(']'));
+    alt((map(ncc::i32, Value::Literal), map(value_list, Value::List)))(i)
+}
+
+impl Value {
+    // Order from the puzzle, where 2 and [[2]] are equal
+    pub fn compare(&self, other: &Self) -> Ordering {
+        fn cmp_lists(l: &[Value], r: &[Value]) -> Ordering {
+            l.iter()
+                .zip(r)
+                .map(|(li, ri)| li.compare(ri))
+                .find(|o| o.is_ne())
+                .unwrap_or_else(|| l.len().cmp(&r.len()))
+        }
+
+        use Value::*;
+        match (self, other) {
+            (Literal(l), Literal(r)) => l.cmp(r),
+            (List(l), List(r)) => cmp_lists(l, r),
+            (Literal(l), List(r)) => cmp_lists(&[Literal(*l)], r),
+            (List(l), Literal(r)) => cmp_lists(l, &[Literal(*r)]),
+        }
+    }
+
+    // Breaks ties between values the puzzle considers equal, literals go first
+    fn structural(&self, other: &Self) -> Ordering {
+        use Value::*;
+        match (self, other) {
+            (Literal(l), Literal(r)) => l.cmp(r),
+            (List(l), List(r)) => l
+                .iter()
+                .zip(r)
+                .map(|(li, ri)| li.structural(ri))
+                .find(|o| o.is_ne())
+                .unwrap_or_else(|| l.len().cmp(&r.len())),
+            (Literal(_), List(_)) => Ordering::Less,
+            (List(_), Literal(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.compare(other).then_with(|| self.structural(other))
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Literal(n) => write!(f, "{n}"),
+            Value::List(values) => {
+                write!(f, "[")?;
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{v}")?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+impl FromStr for Value {
+    type Err = nom::error::Error<String>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match all_consuming(value)(s).finish() {
+            Ok((_, v)) => Ok(v),
+            Err(e) => Err(nom::error::Error::new(e.input.to_string(), e.code)),
+        }
+    }
+}
+
+impl From<&Value> for serde_json::Value {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Literal(n) => (*n).into(),
+            Value::List(values) => values.iter().map(serde_json::Value::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum JsonError {
+    // Only arrays and integers that fit in an i32 make up a packet
+    Unsupported(serde_json::Value),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Unsupported(v) => write!(f, "{v} can't be part of a packet"),
+        }
+    }
+}
+
+impl TryFrom<&serde_json::Value> for Value {
+    type Error = JsonError;
+
+    fn try_from(json: &serde_json::Value) -> Result<Self, Self::Error> {
+        match json {
+            serde_json::Value::Array(values) => values
+                .iter()
+                .map(Value::try_from)
+                .collect::<Result<_, _>>()
+                .map(Value::List),
+            serde_json::Value::Number(n) => n
+                .as_i64()
+                .and_then(|n| i32::try_from(n).ok())
+                .map(Value::Literal)
+                .ok_or_else(|| JsonError::Unsupported(json.clone())),
+            _ => Err(JsonError::Unsupported(json.clone())),
+        }
+    }
+}
+
+// 1-based positions the markers end up at when sorted in with the packets. Markers go
+// before packets equal to them, and no sorting is needed.
+pub fn sorted_positions(packets: &[Value], markers: &[Value]) -> Vec<usize> {
+    markers
+        .iter()
+        .enumerate()
+        .map(|(i, marker)| {
+            let packets = packets.iter().filter(|p| p.compare(marker).is_lt());
+            let markers = markers.iter().enumerate().filter(|(j, m)| {
+                let o = m.compare(marker);
+                o.is_lt() || (o.is_eq() && *j < i)
+            });
+            packets.count() + markers.count() + 1
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(s: &str) -> Value {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for s in [
+            "[]",
+            "[[]]",
+            "7",
+            "[1,[2,[3,[4,[5,6,-7]]]],8,9]",
+            "[[10],[]]",
+        ] {
+            let value = v(s);
+            assert_eq!(value.to_string(), s);
+            let json: serde_json::Value = serde_json::from_str(s).unwrap();
+            assert_eq!(serde_json::Value::from(&value), json);
+            assert_eq!(Value::try_from(&json), Ok(value));
+        }
+        assert!("[1,2".parse::<Value>().is_err());
+        assert!("[1] ".parse::<Value>().is_err());
+        for s in ["[1.5]", "[\"a\"]", "{}", "[4294967296]"] {
+            let json: serde_json::Value = serde_json::from_str(s).unwrap();
+            assert!(Value::try_from(&json).is_err());
+        }
+    }
+    #[test]
+    fn test_total_order() {
+        let (a, b) = (v("2"), v("[[2]]"));
+        assert_eq!(a.compare(&b), Ordering::Equal);
+        assert_eq!(a.cmp(&b), Ordering::Less);
+        assert_eq!(b.cmp(&a), Ordering::Greater);
+        assert_eq!(v("[1,[2]]").cmp(&v("[[1],3]")), Ordering::Less);
+        let mut values = [v("[[2]]"), v("[2]"), v("2"), v("[]"), v("[[]]")];
+        values.sort();
+        assert_eq!(
+            values.iter().map(|v| v.to_string()).collect::<Vec<_>>(),
+            ["[]", "[[]]", "2", "[2]", "[[2]]"]
+        );
+    }
+    #[test]
+    fn test_sorted_positions() {
+        let packets = [v("[1]"), v("[3]"), v("[2]"), v("[[2]]")];
+        assert_eq!(sorted_positions(&packets, &[v("[2]"), v("[0]")]), [3, 1]);
+        assert_eq!(sorted_positions(&packets, &[v("[2]"), v("[2]")]), [2, 3]);
+        assert_eq!(sorted_positions(&[], &[v("[5]")]), [1]);
+    }
+}
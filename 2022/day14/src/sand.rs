@@ -0,0 +1,312 @@
+use std::collections::HashSet;
+
+use crate::{Coord, Position};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Drop {
+    Rested(Position),
+    Abyss,
+    // Sand is already piled up at the source
+    Blocked,
+}
+
+#[derive(Clone, Debug)]
+pub struct Engine {
+    rock: HashSet<Position>,
+    sand: HashSet<Position>,
+    // Lowest rock, anything falling past it without a floor is gone
+    bottom: Coord,
+    floor: Option<Coord>,
+    sources: Vec<Position>,
+    // Path of the last grain from each source, the next one follows it as far as it's free
+    paths: Vec<Vec<Position>>,
+    active: Vec<bool>,
+}
+
+// Cells covered by a segment of any slope. Sand falls diagonally, so diagonal steps get
+// an extra cell at the corner to keep the wall solid
+fn segment((x0, y0): Position, (x1, y1): Position) -> Vec<Position> {
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let mut error = dx + dy;
+    let (mut x, mut y) = (x0, y0);
+    let mut cells = vec![(x, y)];
+    while (x, y) != (x1, y1) {
+        let e2 = 2 * error;
+        if e2 >= dy {
+            error += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            if x != cells.last().unwrap().0 {
+                cells.push((x, y));
+            }
+            error += dx;
+            y += sy;
+        }
+        cells.push((x, y));
+    }
+    cells
+}
+
+impl Engine {
+    pub fn new(paths: &[Vec<Position>]) -> Self {
+        let rock: HashSet<Position> = paths
+            .iter()
+            .flat_map(|path| {
+                let single = (path.len() == 1).then(|| path[0]);
+                path.windows(2)
+                    .flat_map(|w| segment(w[0], w[1]))
+                    .chain(single)
+            })
+            .collect();
+        let bottom = rock.iter().map(|(_, y)| *y).max().unwrap_or(0);
+        Engine {
+            rock,
+            sand: HashSet::new(),
+            bottom,
+            floor: None,
+            sources: Vec::new(),
+            paths: Vec::new(),
+            active: Vec::new(),
+        }
+    }
+
+    pub fn with_source(mut self, source: Position) -> Self {
+        self.sources.push(source);
+        self.paths.push(vec![source]);
+        self.active.push(true);
+        self
+    }
+
+    // Infinite rock floor at this depth
+    pub fn with_floor(mut self, y: Coord) -> Self {
+        self.floor = Some(y);
+        self
+    }
+
+    pub fn bottom(&self) -> Coord {
+        self.bottom
+    }
+
+    pub fn sand(&self) -> usize {
+        self.sand.len()
+    }
+
+    // Everything from the floor down is solid, so a source below it is blocked
+    fn is_free(&self, (x, y): Position) -> bool {
+        self.floor.is_none_or(|floor| y < floor)
+            && !self.rock.contains(&(x, y))
+            && !self.sand.contains(&(x, y))
+    }
+
+    pub fn drop(&mut self, source: usize) -> Drop {
+        let mut path = std::mem::take(&mut self.paths[source]);
+        // Cells only ever fill up, and a grain rests on the cell below it, so
+        // once the top of the path is free the rest of it is too
+        while path.last().is_some_and(|p| !self.is_free(*p)) {
+            path.pop();
+        }
+        let result = match path.pop() {
+            None => Drop::Blocked,
+            Some(mut pos) => loop {
+                if self.floor.is_none() && pos.1 > self.bottom {
+                    path.push(pos);
+                    break Drop::Abyss;
+                }
+                let (x, y) = pos;
+                match [(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)]
+                    .into_iter()
+                    .find(|p| self.is_free(*p))
+                {
+                    Some(next) => {
+                        path.push(pos);
+                        pos = next;
+                    }
+                    None => {
+                        self.sand.insert(pos);
+                        break Drop::Rested(pos);
+                    }
+                }
+            },
+        };
+        self.paths[source] = path;
+        result
+    }
+
+    // Drops a grain from every source still producing sand, returns false once none are
+    pub fn round(&mut self) -> bool {
+        for source in 0..self.sources.len() {
+            if self.active[source] && !matches!(self.drop(source), Drop::Rested(_)) {
+                self.active[source] = false;
+            }
+        }
+        self.active.iter().any(|a| *a)
+    }
+
+    // Runs until no source produces resting sand and returns the amount at rest
+    pub fn run(&mut self) -> usize {
+        while self.round() {}
+        self.sand()
+    }
+
+    // Sand at rest once everything has settled on the floor, without simulating each grain
+    pub fn fill_count(&self) -> Option<usize> {
+        let floor = self.floor?;
+        let mut count = 0;
+        let mut row: HashSet<Coord> = HashSet::new();
+        let top = self.sources.iter().map(|(_, y)| *y).min()?;
+        for y in top..floor {
+            let mut next: HashSet<Coord> = row
+                .iter()
+                .flat_map(|x| [x - 1, *x, x + 1])
+                .chain(self.sources.iter().filter(|s| s.1 == y).map(|s| s.0))
+                .collect();
+            next.retain(|x| !self.rock.contains(&(*x, y)));
+            count += next.len();
+            row = next;
+        }
+        Some(count)
+    }
+
+    pub fn render(&self) -> String {
+        let cells = || self.rock.iter().chain(&self.sand).chain(&self.sources);
+        let (x0, x1) = cells().fold((Coord::MAX, Coord::MIN), |(a, b), (x, _)| {
+            (a.min(*x), b.max(*x))
+        });
+        let y0 = cells().map(|(_, y)| *y).min().unwrap_or(0);
+        let y1 = self.floor.unwrap_or(self.bottom);
+        (y0..=y1)
+            .map(|y| {
+                (x0..=x1)
+                    .map(|x| {
+                        if self.sand.contains(&(x, y)) {
+                            'o'
+                        } else if self.rock.contains(&(x, y)) || self.floor == Some(y) {
+                            '#'
+                        } else if self.sources.contains(&(x, y)) {
+                            '+'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PATHS: [&[Position]; 2] = [
+        &[(498, 4), (498, 6), (496, 6)],
+        &[(503, 4), (502, 4), (502, 9), (494, 9)],
+    ];
+
+    fn engine() -> Engine {
+        let paths: Vec<Vec<Position>> = PATHS.iter().map(|p| p.to_vec()).collect();
+        Engine::new(&paths).with_source((500, 0))
+    }
+
+    #[test]
+    fn test_frames() {
+        let mut e = engine();
+        for _ in 0..5 {
+            e.round();
+        }
+        assert_eq!(
+            e.render(),
+            [
+                "......+...",
+                "..........",
+                "..........",
+                "..........",
+                "....#...##",
+                "....#...#.",
+                "..###...#.",
+                "......o.#.",
+                "....oooo#.",
+                "#########.",
+            ]
+            .join("\n")
+        );
+        assert_eq!(e.run(), 24);
+        assert_eq!(
+            e.render(),
+            [
+                "......+...",
+                "..........",
+                "......o...",
+                ".....ooo..",
+                "....#ooo##",
+                "...o#ooo#.",
+                "..###ooo#.",
+                "....oooo#.",
+                ".o.ooooo#.",
+                "#########.",
+            ]
+            .join("\n")
+        );
+    }
+    #[test]
+    fn test_fill_count() {
+        let mut e = engine().with_floor(11);
+        assert_eq!(e.fill_count(), Some(93));
+        assert_eq!(e.run(), 93);
+        assert_eq!(e.drop(0), Drop::Blocked);
+        assert_eq!(engine().fill_count(), None);
+    }
+    #[test]
+    fn test_diagonals() {
+        assert_eq!(
+            segment((0, 0), (2, -2)),
+            [(0, 0), (1, 0), (1, -1), (2, -1), (2, -2)]
+        );
+        assert_eq!(segment((3, 0), (3, 2)), [(3, 0), (3, 1), (3, 2)]);
+        assert_eq!(
+            segment((0, 0), (4, 2)),
+            [(0, 0), (1, 0), (1, 1), (2, 1), (3, 1), (3, 2), (4, 2)]
+        );
+        // A V-shaped funnel with diagonal sides holds sand
+        let mut e = Engine::new(&[vec![(0, 0), (3, 3), (6, 0)]]).with_source((3, -1));
+        assert_eq!(e.run(), 6);
+        assert_eq!(
+            e.render(),
+            ["...o...", "##ooo.#", ".##oo##", "..####.", "...##.."].join("\n")
+        );
+    }
+    #[test]
+    fn test_multiple_sources() {
+        // Two sources over a flat floor pile up overlapping triangles
+        let mut e = Engine::new(&[])
+            .with_source((0, 0))
+            .with_source((4, 0))
+            .with_floor(3);
+        let filled = e.fill_count();
+        assert_eq!(filled, Some(17));
+        assert_eq!(e.run(), 17);
+        assert_eq!(
+            e.render(),
+            ["..o...o..", ".ooo.ooo.", "ooooooooo", "#########"].join("\n")
+        );
+    }
+    #[test]
+    fn test_source_below_floor() {
+        for source in [(0, 3), (0, 7)] {
+            let mut e = Engine::new(&[]).with_source(source).with_floor(3);
+            assert_eq!(e.fill_count(), Some(0));
+            assert_eq!(e.drop(0), Drop::Blocked);
+            assert_eq!(e.run(), 0);
+        }
+        // Only the source above the floor produces sand
+        let mut e = Engine::new(&[])
+            .with_source((0, 2))
+            .with_source((5, 9))
+            .with_floor(3);
+        assert_eq!(e.fill_count(), Some(1));
+        assert_eq!(e.run(), 1);
+    }
+}
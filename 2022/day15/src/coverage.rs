@@ -0,0 +1,197 @@
+use std::ops::RangeInclusive;
+
+use crate::{Coord, Position, Sensor};
+
+// Number of integers in lo..=hi with the given parity
+fn parity_count(lo: Coord, hi: Coord, parity: Coord) -> Coord {
+    if lo > hi {
+        0
+    } else {
+        (hi - parity).div_euclid(2) - (lo - 1 - parity).div_euclid(2)
+    }
+}
+
+// Sorted, non-overlapping and non-adjacent ranges
+fn merged(mut ranges: Vec<RangeInclusive<Coord>>) -> Vec<RangeInclusive<Coord>> {
+    ranges.sort_by_key(|r| *r.start());
+    let mut result: Vec<RangeInclusive<Coord>> = Vec::with_capacity(ranges.len());
+    for r in ranges {
+        match result.last_mut() {
+            Some(last) if *r.start() <= last.end() + 1 => {
+                *last = *last.start()..=*last.end().max(r.end());
+            }
+            _ => result.push(r),
+        }
+    }
+    result
+}
+
+// Sensor diamonds turned 45 degrees with u = x + y and v = x - y, which makes each of
+// them a square. A cell (x, y) maps to a (u, v) with u and v of the same parity.
+pub struct Coverage {
+    squares: Vec<(RangeInclusive<Coord>, RangeInclusive<Coord>)>,
+    diamonds: Vec<(Position, Coord)>,
+}
+
+impl Coverage {
+    pub fn new(sensors: &[Sensor]) -> Self {
+        let diamonds: Vec<(Position, Coord)> = sensors
+            .iter()
+            .map(|&((x, y), (bx, by))| ((x, y), (x - bx).abs() + (y - by).abs()))
+            .collect();
+        let squares = diamonds
+            .iter()
+            .map(|&((x, y), r)| (x + y - r..=x + y + r, x - y - r..=x - y + r))
+            .collect();
+        Coverage { squares, diamonds }
+    }
+
+    // Covered columns on a single row
+    pub fn row(&self, y: Coord) -> Vec<RangeInclusive<Coord>> {
+        merged(
+            self.diamonds
+                .iter()
+                .filter_map(|&((sx, sy), r)| {
+                    let d = r - (sy - y).abs();
+                    (d >= 0).then(|| sx - d..=sx + d)
+                })
+                .collect(),
+        )
+    }
+
+    // Splits the u axis where any square starts or ends, each slab has a fixed set of
+    // covered v ranges
+    fn slabs(&self, extra: &[Coord]) -> Vec<(RangeInclusive<Coord>, Vec<RangeInclusive<Coord>>)> {
+        let mut bounds: Vec<Coord> = self
+            .squares
+            .iter()
+            .flat_map(|(u, _)| [*u.start(), u.end() + 1])
+            .chain(extra.iter().copied())
+            .collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+        bounds
+            .windows(2)
+            .map(|w| {
+                let active = self
+                    .squares
+                    .iter()
+                    .filter(|(u, _)| u.contains(&w[0]))
+                    .map(|(_, v)| v.clone())
+                    .collect();
+                (w[0]..=w[1] - 1, merged(active))
+            })
+            .collect()
+    }
+
+    // Number of cells within reach of any sensor
+    pub fn covered_area(&self) -> Coord {
+        self.slabs(&[])
+            .into_iter()
+            .map(|(u, vs)| {
+                let (even, odd) = vs.iter().fold((0, 0), |(e, o), v| {
+                    (
+                        e + parity_count(*v.start(), *v.end(), 0),
+                        o + parity_count(*v.start(), *v.end(), 1),
+                    )
+                });
+                parity_count(*u.start(), *u.end(), 0) * even
+                    + parity_count(*u.start(), *u.end(), 1) * odd
+            })
+            .sum()
+    }
+
+    // Cells in the rectangle between `min` and `max` that no sensor reaches, row by row
+    // within each slab. Only the cells found are visited, so huge areas are fine.
+    pub fn uncovered(&self, min: Position, max: Position) -> impl Iterator<Item = Position> {
+        let ((x0, y0), (x1, y1)) = (min, max);
+        let (u_min, u_max) = (x0 + y0, x1 + y1);
+        let (v_min, v_max) = (x0 - y1, x1 - y0);
+        let mut regions = Vec::new();
+        if u_min <= u_max {
+            for (u, covered) in self.slabs(&[u_min, u_max + 1]) {
+                if *u.end() < u_min || *u.start() > u_max {
+                    continue;
+                }
+                let mut v = v_min;
+                for c in covered.iter().chain([&(v_max + 1..=v_max + 1)]) {
+                    if *c.start() > v {
+                        regions.push((u.clone(), v..=(*c.start() - 1).min(v_max)));
+                    }
+                    v = v.max(c.end() + 1);
+                    if v > v_max {
+                        break;
+                    }
+                }
+            }
+        }
+        regions.into_iter().flat_map(move |(u, v)| {
+            // Limits on u for which the rectangle has cells in this region
+            let (va, vb) = (*v.start(), *v.end());
+            let lo = [*u.start(), va + 2 * y0, 2 * x0 - vb, x0 + y0];
+            let hi = [*u.end(), 2 * x1 - va, vb + 2 * y1, x1 + y1];
+            let (lo, hi) = (*lo.iter().max().unwrap(), *hi.iter().min().unwrap());
+            (lo..=hi).flat_map(move |u| {
+                let from = va.max(2 * x0 - u).max(u - 2 * y1);
+                let to = vb.min(2 * x1 - u).min(u - 2 * y0);
+                let from = from + (from - u).rem_euclid(2);
+                (from..=to)
+                    .step_by(2)
+                    .map(move |v| ((u + v) / 2, (u - v) / 2))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use nom::Finish;
+
+    fn coverage() -> Coverage {
+        let (_, sensors) = parse(include_str!("../test_input.txt"))
+            .finish()
+            .expect("Parse error");
+        Coverage::new(&sensors)
+    }
+
+    fn is_covered(c: &Coverage, (x, y): Position) -> bool {
+        c.diamonds
+            .iter()
+            .any(|&((sx, sy), r)| (sx - x).abs() + (sy - y).abs() <= r)
+    }
+
+    #[test]
+    fn test_row() {
+        assert_eq!(coverage().row(10), [-2..=24]);
+        assert_eq!(coverage().row(-10), [2..=2]);
+        assert!(coverage().row(100).is_empty());
+    }
+    #[test]
+    fn test_covered_area() {
+        let c = coverage();
+        let brute = (-20..=45)
+            .flat_map(|y| (-20..=45).map(move |x| (x, y)))
+            .filter(|p| is_covered(&c, *p))
+            .count();
+        assert_eq!(c.covered_area(), brute as Coord);
+    }
+    #[test]
+    fn test_uncovered() {
+        let c = coverage();
+        assert_eq!(
+            c.uncovered((0, 0), (20, 20)).collect::<Vec<_>>(),
+            [(14, 11)]
+        );
+        let (min, max) = ((-7, -3), (31, 26));
+        let mut found: Vec<Position> = c.uncovered(min, max).collect();
+        found.sort_by_key(|(x, y)| (*y, *x));
+        let brute: Vec<Position> = (min.1..=max.1)
+            .flat_map(|y| (min.0..=max.0).map(move |x| (x, y)))
+            .filter(|p| !is_covered(&c, *p))
+            .collect();
+        assert_eq!(found, brute);
+        assert_eq!(c.uncovered((5, 5), (4, 4)).count(), 0);
+    }
+}
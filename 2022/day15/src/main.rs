@@ -6,6 +6,10 @@ use nom::{
     Finish,
 };
 
+use coverage::Coverage;
+
+mod coverage;
+
 const PUZZLE_INPUT: &str = include_str!("../puzzle_input.txt");
 
 type Coord = i64;
@@ -25,61 +29,29 @@ fn parse(input: &str) -> nom::IResult<&str, Sensors> {
     separated_list0(ncc::line_ending, line)(input)
 }
 
-fn covered_on_row(((x, y), (bx, by)): &Sensor, row: i64) -> std::ops::Range<Coord> {
-    let d = (x.abs_diff(*bx) + y.abs_diff(*by)).saturating_sub(y.abs_diff(row)) as i64;
-    if d > 0 {
-        x - d..(x + d + 1)
-    } else {
-        0..0
-    }
-}
-fn merge_ranges(xs: &mut Vec<std::ops::Range<Coord>>) {
-    'next: loop {
-        for i in 0..xs.len() - 1 {
-            for j in i + 1..xs.len() {
-                let a = &xs[i];
-                let b = &xs[j];
-                if a.contains(&b.start)
-                    || a.contains(&b.end)
-                    || b.contains(&a.start)
-                    || b.contains(&a.end)
-                    || a.start.abs_diff(b.end) == 0
-                    || a.end.abs_diff(b.start) == 0
-                {
-                    xs[i] = a.start.min(b.start)..a.end.max(b.end);
-                    xs.swap_remove(j);
-                    continue 'next;
-                }
-            }
-        }
-        return;
-    }
-}
 fn part1(sensors: &Sensors, row: i64) -> i64 {
-    let mut ranges: Vec<_> = sensors.iter().map(|s| covered_on_row(s, row)).collect();
-    merge_ranges(&mut ranges);
-    let covered: i64 = ranges.into_iter().map(|r| r.end - r.start).sum();
-    covered - 1
+    let covered: i64 = Coverage::new(sensors)
+        .row(row)
+        .into_iter()
+        .map(|r| r.end() - r.start() + 1)
+        .sum();
+    // Known beacons are always within reach of their sensor
+    let mut beacons: Vec<Coord> = sensors
+        .iter()
+        .filter(|(_, (_, y))| *y == row)
+        .map(|(_, (x, _))| *x)
+        .collect();
+    beacons.sort_unstable();
+    beacons.dedup();
+    covered - beacons.len() as i64
 }
 
 fn part2(sensors: &Sensors, min: Coord, max: Coord) -> Option<i64> {
     let frequency = |(x, y): Position| x * 4_000_000 + y;
-    for y in min..=max {
-        let mut x = min;
-        while x <= max {
-            if let Some(range) = sensors
-                .iter()
-                .map(|s| covered_on_row(s, y))
-                .filter(|r| r.contains(&x))
-                .next()
-            {
-                x = range.end;
-            } else {
-                return Some(frequency((x, y)));
-            }
-        }
-    }
-    None
+    Coverage::new(sensors)
+        .uncovered((min, min), (max, max))
+        .next()
+        .map(frequency)
 }
 
 fn main() {
@@ -89,6 +61,9 @@ fn main() {
         "Part 2: {}",
         part2(&sensors, 0, 4_000_000).expect("No solution found")
     );
+    if std::env::args().nth(1).as_deref() == Some("area") {
+        println!("Covered area: {}", Coverage::new(&sensors).covered_area());
+    }
 }
 
 #[cfg(test)]
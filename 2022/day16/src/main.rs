@@ -1,4 +1,3 @@
-use itertools::Itertools;
 use nom::{
     bytes::complete::tag,
     character::complete as ncc,
@@ -7,16 +6,15 @@ use nom::{
     branch::alt,
     Finish,
 };
-use std::collections::{HashMap, HashSet, VecDeque};
+use planner::{Plan, Planner, PlannerError};
+
+mod planner;
 
 const PUZZLE_INPUT: &str = include_str!("../puzzle_input.txt");
 type Valve<'a> = (&'a str, u32, Vec<&'a str>);
 type Valves<'a> = Vec<Valve<'a>>;
-type Location = usize;
-type Map = HashMap<Location, HashSet<Location>>;
-type Flow = u32;
 
-fn parse(input: &str) -> nom::IResult<&str, Valves> {
+fn parse(input: &str) -> nom::IResult<&str, Valves<'_>> {
     let line = tuple((
         preceded(tag("Valve "), ncc::alpha1),
         preceded(tag(" has flow rate="), ncc::u32),
@@ -29,104 +27,25 @@ fn parse(input: &str) -> nom::IResult<&str, Valves> {
     separated_list1(ncc::line_ending, line)(input)
 }
 
-fn distance(start: Location, end: Location, map: &Map) -> Option<u32> {
-    let mut visited = HashSet::new();
-    let mut stack: VecDeque<_> = [(start, 0)].into();
-    while let Some((pos, steps)) = stack.pop_front() {
-        visited.insert(pos);
-        if pos == end {
-            return Some(steps);
-        } else {
-            let neigbors = map.get(&pos).unwrap().iter().filter(|n| !visited.contains(n)).map(|n| (*n, steps + 1));
-            stack.extend(neigbors);
-        }
-    }
-    None
+fn release_pressure<'a>(valves: &Valves<'a>, agents: usize, duration: u32) -> Result<Plan<'a>, PlannerError> {
+    Ok(Planner::new(valves, "AA")?.plan(agents, duration))
 }
 
-fn release_pressure<const N: usize>(valves: &Valves, duration: u32) -> u32 {
-    let name_id_map: HashMap<&str, usize> = valves
-        .iter()
-        .flat_map(|v| v.2.iter())
-        .enumerate()
-        .map(|(i, v)| (*v, i))
-        .collect();
-    let get_id = move |name| *name_id_map.get(name).unwrap();
-    let start = get_id("AA");
-    let mut map = Map::new();
-    for (name, _, adjacent_names) in valves.iter() {
-        let location = get_id(name);
-        let adjacent: Vec<_> = adjacent_names.iter().map(|n| get_id(*n)).collect();
-        map.entry(location).or_default().extend(adjacent.iter());
-        for a in adjacent {
-            map.entry(a).or_default().insert(location);
-        }
-    }
-    let costs: HashMap<(Location, Location), u32> = map.keys().flat_map(|l| {
-        map.keys()
-         .map(|x| ((*l, *x), distance(*l, *x, &map).unwrap()))
-    }).collect();
-    let actionable: HashSet<_> = valves
-        .iter()
-        .filter_map(|(name, flow, _)| (*flow > 0).then_some((get_id(name), *flow)))
-        .collect();
-    let mut best = 0; //release_pressure(&costs, actionable.iter(), start);
-    type Plan = Vec<(Location, Flow)>;
-    let mut queue: VecDeque<[Plan; N]> = [[0; N].map(|_| vec![(start, 0u32)])].into();
-    while let Some(plans) = queue.pop_back() {
-            let mut left = actionable.clone();
-            let mut released: u32 = 0;
-            let mut time_left = 0;
-            let mut next = 0;
-            for (i, plan) in plans.iter().enumerate() {
-                let mut time = duration;
-                let mut it = plan.iter();
-                let (first, _) = *it.next().unwrap();
-                released += it.scan(first, |pos, &(next, flow)| {
-                    left.remove(&(next, flow));
-                    time = time.saturating_sub(*costs.get(&(*pos, next)).unwrap() + 1);
-                    *pos = next;
-                    Some(flow * time)
-                })
-                .sum::<u32>();
-                if time > time_left {
-                    time_left = time;
-                    next = i;
-                }
-            }
-            if left.is_empty() {
-                if released > best {
-                    best = released;
-                }
-            } else {
-                // Estimate ability to release pressure by assuming opening the most pressured
-                // valve each turn
-                let sorted_left: Vec<_> = left.iter().map(|(_, flow)| *flow).sorted().collect();
-                let times = (0..=time_left).rev();
-                let estimate_left: u32 = sorted_left.iter().rev().zip(times).map(|(flow, t)| flow * t).sum::<u32>() * N as u32;
-                let potential: u32 = released + estimate_left;
-                if potential > best {
-                    left.into_iter().for_each(|x| {
-                        let mut new_plan = plans.clone();
-                        new_plan[next].push(x);
-                        queue.push_back(new_plan);
-                    })
-                }
-            }
-        
-    }
-    return best;
-}
 fn part1(valves: &Valves) -> u32 {
-    release_pressure::<1>(valves, 30)
+    release_pressure(valves, 1, 30).expect("Invalid valves").released
 }
 
 fn part2(valves: &Valves) -> u32 {
-    release_pressure::<2>(valves, 26)
+    release_pressure(valves, 2, 26).expect("Invalid valves").released
 }
 
 fn main() {
     let (_, valves) = parse(PUZZLE_INPUT).finish().expect("Parse error");
+    if std::env::args().nth(1).as_deref() == Some("explain") {
+        println!("Part 1:\n{}", release_pressure(&valves, 1, 30).expect("Invalid valves"));
+        println!("Part 2:\n{}", release_pressure(&valves, 2, 26).expect("Invalid valves"));
+        return;
+    }
     println!("Part 1: {}", part1(&valves));
     println!("Part 2: {}", part2(&valves));
 }
@@ -156,4 +75,37 @@ mod test {
         let (_, valves) = parse(PUZZLE_INPUT).finish().expect("Parse error");
         assert_eq!(part2(&valves), 2343);
     }
+    #[test]
+    fn test_schedule() {
+        let (_, valves) = parse(TEST_INPUT).finish().expect("Parse error");
+        let plan = release_pressure(&valves, 1, 30).unwrap();
+        let opened: Vec<_> = plan.schedules[0].iter().map(|o| (o.valve, o.minute)).collect();
+        assert_eq!(opened, [("DD", 2), ("BB", 5), ("JJ", 9), ("HH", 17), ("EE", 21), ("CC", 24)]);
+        let total: u32 = plan.schedules[0].iter().map(|o| o.released).sum();
+        assert_eq!(total, plan.released);
+    }
+    #[test]
+    fn test_more_agents() {
+        let (_, valves) = parse(TEST_INPUT).finish().expect("Parse error");
+        let plan = release_pressure(&valves, 2, 26).unwrap();
+        assert_eq!(plan.schedules.len(), 2);
+        let mut opened: Vec<_> = plan.schedules.iter().flatten().map(|o| o.valve).collect();
+        opened.sort();
+        assert_eq!(opened, ["BB", "CC", "DD", "EE", "HH", "JJ"]);
+        // Three agents can't do worse than two, and plenty of time can't do worse than less
+        assert!(release_pressure(&valves, 3, 26).unwrap().released >= plan.released);
+        assert!(release_pressure(&valves, 1, 40).unwrap().released > part1(&valves));
+        assert_eq!(release_pressure(&valves, 1, 1).unwrap().released, 0);
+    }
+    #[test]
+    fn test_invalid_valves() {
+        let (_, valves) = parse("Valve AA has flow rate=0; tunnel leads to valve BB").finish().unwrap();
+        assert_eq!(release_pressure(&valves, 1, 30).err(), Some(PlannerError::UnknownValve("BB".into())));
+        let (_, valves) = parse("Valve BB has flow rate=1; tunnel leads to valve BB").finish().unwrap();
+        assert_eq!(release_pressure(&valves, 1, 30).err(), Some(PlannerError::UnknownValve("AA".into())));
+        let names: Vec<String> = (0..21).map(|i| format!("V{}", char::from(b'A' + i))).collect();
+        let scan: String = names.iter().map(|n| format!("Valve {n} has flow rate=1; tunnel leads to valve VA\n")).collect();
+        let (_, valves) = parse(scan.trim_end()).finish().unwrap();
+        assert_eq!(Planner::new(&valves, "VA").err(), Some(PlannerError::TooManyValves(21)));
+    }
 }
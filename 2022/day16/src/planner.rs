@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::fmt;
+
+type Flow = u32;
+type Mask = usize;
+
+// Bitmasks over useful valves are used as indices, so keep the table size sane
+const MAX_USEFUL_VALVES: usize = 20;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Opening<'a> {
+    pub valve: &'a str,
+    pub minute: u32,
+    pub released: Flow,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Plan<'a> {
+    pub released: Flow,
+    pub schedules: Vec<Vec<Opening<'a>>>,
+}
+
+impl fmt::Display for Plan<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, schedule) in self.schedules.iter().enumerate() {
+            write!(f, "Agent {}:", i + 1)?;
+            for o in schedule {
+                write!(f, " {}@{} (+{})", o.valve, o.minute, o.released)?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "Total released: {}", self.released)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlannerError {
+    // A tunnel or the start names a valve that isn't in the scan
+    UnknownValve(String),
+    TooManyValves(usize),
+}
+
+impl fmt::Display for PlannerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlannerError::UnknownValve(name) => write!(f, "there is no valve {name}"),
+            PlannerError::TooManyValves(n) => write!(
+                f,
+                "{n} valves with flow, at most {MAX_USEFUL_VALVES} are supported"
+            ),
+        }
+    }
+}
+
+pub struct Planner<'a> {
+    names: Vec<&'a str>,
+    flows: Vec<Flow>,
+    // Distances between useful valves, with the start as the last row
+    distances: Vec<Vec<u32>>,
+}
+
+type Route = Vec<(usize, u32)>;
+
+impl<'a> Planner<'a> {
+    pub fn new(
+        valves: &[(&'a str, Flow, Vec<&'a str>)],
+        start: &str,
+    ) -> Result<Self, PlannerError> {
+        let ids: HashMap<&str, usize> = valves.iter().enumerate().map(|(i, v)| (v.0, i)).collect();
+        let id = |name: &str| {
+            ids.get(name)
+                .copied()
+                .ok_or_else(|| PlannerError::UnknownValve(name.to_string()))
+        };
+        let n = valves.len();
+        let mut dist = vec![vec![u32::MAX / 2; n]; n];
+        for (i, (_, _, tunnels)) in valves.iter().enumerate() {
+            dist[i][i] = 0;
+            for t in tunnels {
+                dist[i][id(t)?] = 1;
+            }
+        }
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    dist[i][j] = dist[i][j].min(dist[i][k] + dist[k][j]);
+                }
+            }
+        }
+
+        let useful: Vec<usize> = (0..n).filter(|&i| valves[i].1 > 0).collect();
+        if useful.len() > MAX_USEFUL_VALVES {
+            return Err(PlannerError::TooManyValves(useful.len()));
+        }
+        let rows = useful.iter().copied().chain([id(start)?]);
+        Ok(Planner {
+            names: useful.iter().map(|&i| valves[i].0).collect(),
+            flows: useful.iter().map(|&i| valves[i].1).collect(),
+            distances: rows
+                .map(|from| useful.iter().map(|&to| dist[from][to]).collect())
+                .collect(),
+        })
+    }
+
+    fn explore(
+        &self,
+        pos: usize,
+        time: u32,
+        mask: Mask,
+        released: Flow,
+        route: &mut Route,
+        best: &mut [(Flow, Route)],
+    ) {
+        if released > best[mask].0 {
+            best[mask] = (released, route.clone());
+        }
+        for next in (0..self.flows.len()).filter(|i| mask & (1 << i) == 0) {
+            let cost = self.distances[pos][next] + 1;
+            if cost < time {
+                let left = time - cost;
+                route.push((next, left));
+                self.explore(
+                    next,
+                    left,
+                    mask | 1 << next,
+                    released + self.flows[next] * left,
+                    route,
+                    best,
+                );
+                route.pop();
+            }
+        }
+    }
+
+    // Best single agent route opening exactly the valves in each mask
+    fn single(&self, duration: u32) -> Vec<(Flow, Route)> {
+        let mut best = vec![(0, Route::new()); 1 << self.flows.len()];
+        self.explore(
+            self.flows.len(),
+            duration,
+            0,
+            0,
+            &mut Route::new(),
+            &mut best,
+        );
+        best
+    }
+
+    pub fn plan(&self, agents: usize, duration: u32) -> Plan<'a> {
+        assert!(agents > 0, "At least one agent is needed");
+        let size = 1 << self.flows.len();
+        let best = self.single(duration);
+
+        // Best value using valves within each mask, and the exact mask achieving it
+        let mut within: Vec<(Flow, Mask)> =
+            best.iter().enumerate().map(|(m, (f, _))| (*f, m)).collect();
+        for bit in 0..self.flows.len() {
+            for mask in 0..size {
+                if mask & (1 << bit) != 0 && within[mask ^ (1 << bit)].0 > within[mask].0 {
+                    within[mask] = within[mask ^ (1 << bit)];
+                }
+            }
+        }
+
+        // Each extra agent takes a disjoint subset of the valves
+        let mut layers: Vec<Vec<(Flow, Mask)>> = vec![within.clone()];
+        for _ in 1..agents {
+            let prev = layers.last().unwrap();
+            let next = (0..size)
+                .map(|mask| {
+                    let mut choice = (prev[mask].0, 0);
+                    let mut sub = mask;
+                    while sub > 0 {
+                        let value = within[sub].0 + prev[mask ^ sub].0;
+                        if value > choice.0 {
+                            choice = (value, sub);
+                        }
+                        sub = (sub - 1) & mask;
+                    }
+                    choice
+                })
+                .collect();
+            layers.push(next);
+        }
+
+        let mut mask = size - 1;
+        let mut schedules = Vec::new();
+        for layer in layers[1..].iter().rev() {
+            let sub = layer[mask].1;
+            schedules.push(self.schedule(&best[within[sub].1].1, duration));
+            mask ^= sub;
+        }
+        schedules.push(self.schedule(&best[within[mask].1].1, duration));
+        schedules.reverse();
+        Plan {
+            released: layers.last().unwrap()[size - 1].0,
+            schedules,
+        }
+    }
+
+    fn schedule(&self, route: &Route, duration: u32) -> Vec<Opening<'a>> {
+        route
+            .iter()
+            .map(|&(valve, left)| Opening {
+                valve: self.names[valve],
+                minute: duration - left,
+                released: self.flows[valve] * left,
+            })
+            .collect()
+    }
+}
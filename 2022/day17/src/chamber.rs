@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Direction;
+
+pub const PIECES: &str = include_str!("../pieces.txt");
+
+// Cells of a piece as bitmasks, bottom row first, with bit 0 at the left edge
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shape {
+    rows: Vec<u64>,
+    width: usize,
+}
+
+// Width of the chamber when the pieces file doesn't say
+pub const DEFAULT_WIDTH: usize = 7;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pieces {
+    pub width: usize,
+    pub shapes: Vec<Shape>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChamberError {
+    InvalidCell { piece: usize, cell: char },
+    // Rows are 64 bit masks
+    ColumnLimit { piece: usize },
+    EmptyPiece { piece: usize },
+    InvalidWidth(String),
+    NoPieces,
+    NoJets,
+    Width(usize),
+    // Pieces appear two cells from the left wall and must fit there
+    TooWide { piece: usize },
+}
+
+impl fmt::Display for ChamberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChamberError::InvalidCell { piece, cell } => {
+                write!(f, "piece {piece} has an invalid cell {cell:?}")
+            }
+            ChamberError::ColumnLimit { piece } => {
+                write!(f, "piece {piece} has cells past column 64")
+            }
+            ChamberError::EmptyPiece { piece } => write!(f, "piece {piece} has no cells"),
+            ChamberError::InvalidWidth(line) => write!(f, "invalid width line {line:?}"),
+            ChamberError::NoPieces => write!(f, "no pieces"),
+            ChamberError::NoJets => write!(f, "no jets"),
+            ChamberError::Width(width) => write!(f, "chamber can't be {width} wide"),
+            ChamberError::TooWide { piece } => write!(f, "piece {piece} doesn't fit the chamber"),
+        }
+    }
+}
+
+// An optional `width <n>` line for the chamber, then pieces drawn with '#' and '.',
+// separated by blank lines
+pub fn parse_pieces(text: &str) -> Result<Pieces, ChamberError> {
+    let text = text.trim_start();
+    let (width, text) = match text.strip_prefix("width") {
+        Some(rest) => {
+            let (line, rest) = rest.split_once('\n').unwrap_or((rest, ""));
+            let width = line
+                .trim()
+                .parse()
+                .map_err(|_| ChamberError::InvalidWidth(format!("width{line}")))?;
+            (width, rest)
+        }
+        None => (DEFAULT_WIDTH, text),
+    };
+    let mut shapes = Vec::new();
+    for (piece, block) in text
+        .split("\n\n")
+        .filter(|b| !b.trim().is_empty())
+        .enumerate()
+    {
+        let mut rows = Vec::new();
+        for line in block.lines().rev().map(str::trim).filter(|l| !l.is_empty()) {
+            let mut row: u64 = 0;
+            for (x, cell) in line.chars().enumerate() {
+                match cell {
+                    '#' if x < 64 => row |= 1 << x,
+                    '#' => return Err(ChamberError::ColumnLimit { piece }),
+                    '.' => (),
+                    _ => return Err(ChamberError::InvalidCell { piece, cell }),
+                }
+            }
+            rows.push(row);
+        }
+        // Drop empty rows and columns around the piece
+        rows.retain(|r| *r != 0);
+        if rows.is_empty() {
+            return Err(ChamberError::EmptyPiece { piece });
+        }
+        let left = rows.iter().map(|r| r.trailing_zeros()).min().unwrap_or(0);
+        rows.iter_mut().for_each(|r| *r >>= left);
+        let width = rows
+            .iter()
+            .map(|r| 64 - r.leading_zeros() as usize)
+            .max()
+            .unwrap_or(0);
+        shapes.push(Shape { rows, width });
+    }
+    if shapes.is_empty() {
+        return Err(ChamberError::NoPieces);
+    }
+    Ok(Pieces { width, shapes })
+}
+
+// Pieces and jets to use next, with the open cells a falling rock can still reach from
+// the top, one mask per row going down. Whenever this repeats, so does everything after it.
+type StateKey = (usize, usize, Vec<u64>);
+
+// How far down the reachable area is followed. Deeper holes only matter once rocks
+// fill everything above them, so they're treated as closed to keep the key bounded.
+const STATE_DEPTH: usize = 64;
+
+#[derive(Clone, Debug)]
+pub struct Chamber {
+    width: usize,
+    pieces: Vec<Shape>,
+    jets: Vec<Direction>,
+    rows: Vec<u64>,
+    rocks: usize,
+    jet: usize,
+}
+
+impl Chamber {
+    pub fn new(
+        width: usize,
+        pieces: Vec<Shape>,
+        jets: Vec<Direction>,
+    ) -> Result<Self, ChamberError> {
+        if width == 0 || width > 64 {
+            return Err(ChamberError::Width(width));
+        }
+        if pieces.is_empty() {
+            return Err(ChamberError::NoPieces);
+        }
+        if jets.is_empty() {
+            return Err(ChamberError::NoJets);
+        }
+        if let Some(piece) = pieces.iter().position(|p| p.rows.is_empty()) {
+            return Err(ChamberError::EmptyPiece { piece });
+        }
+        if let Some(piece) = pieces.iter().position(|p| p.width + 2 > width) {
+            return Err(ChamberError::TooWide { piece });
+        }
+        Ok(Chamber {
+            width,
+            pieces,
+            jets,
+            rows: Vec::new(),
+            rocks: 0,
+            jet: 0,
+        })
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn rocks(&self) -> usize {
+        self.rocks
+    }
+
+    fn fits(&self, shape: &Shape, x: usize, y: usize) -> bool {
+        shape
+            .rows
+            .iter()
+            .enumerate()
+            .all(|(i, r)| self.rows.get(y + i).is_none_or(|row| row & (r << x) == 0))
+    }
+
+    pub fn drop_rock(&mut self) {
+        let shape = &self.pieces[self.rocks % self.pieces.len()];
+        let (mut x, mut y): (usize, usize) = (2, self.rows.len() + 3);
+        loop {
+            let direction = self.jets[self.jet];
+            self.jet = (self.jet + 1) % self.jets.len();
+            let pushed = match direction {
+                Direction::Left => x.checked_sub(1),
+                Direction::Right => (x + shape.width < self.width).then_some(x + 1),
+            };
+            if let Some(pushed) = pushed.filter(|px| self.fits(shape, *px, y)) {
+                x = pushed;
+            }
+            if y > 0 && self.fits(shape, x, y - 1) {
+                y -= 1;
+            } else {
+                break;
+            }
+        }
+        let top = y + shape.rows.len();
+        if top > self.rows.len() {
+            self.rows.resize(top, 0);
+        }
+        for (i, r) in shape.rows.iter().enumerate() {
+            self.rows[y + i] |= r << x;
+        }
+        self.rocks += 1;
+    }
+
+    fn state(&self) -> StateKey {
+        let depth = self.rows.len().min(STATE_DEPTH);
+        let full = u64::MAX >> (64 - self.width);
+        let open: Vec<u64> = self
+            .rows
+            .iter()
+            .rev()
+            .take(depth)
+            .map(|r| !r & full)
+            .collect();
+        let mut reached = vec![0u64; depth];
+        // Flood fill from the open cells of the top row
+        let mut stack: Vec<(usize, usize)> = (0..self.width)
+            .filter(|x| open.first().is_some_and(|r| r & (1 << x) != 0))
+            .map(|x| (x, 0))
+            .collect();
+        while let Some((x, d)) = stack.pop() {
+            if reached[d] & (1 << x) != 0 {
+                continue;
+            }
+            reached[d] |= 1 << x;
+            let neighbours = [
+                (x.checked_sub(1), Some(d)),
+                (Some(x + 1), Some(d)),
+                (Some(x), d.checked_sub(1)),
+                (Some(x), Some(d + 1)),
+            ];
+            for (nx, nd) in neighbours {
+                if let (Some(nx), Some(nd)) = (nx, nd) {
+                    if nx < self.width && nd < depth && open[nd] & (1 << nx) != 0 {
+                        stack.push((nx, nd));
+                    }
+                }
+            }
+        }
+        (self.rocks % self.pieces.len(), self.jet, reached)
+    }
+
+    // Height once `rocks` rocks in total have fallen, skipping over repeating stretches
+    pub fn height_after(mut self, rocks: usize) -> usize {
+        let mut seen: HashMap<StateKey, (usize, usize)> = HashMap::new();
+        let mut skipped = None;
+        while self.rocks < rocks {
+            if skipped.is_none() {
+                if let Some((r, h)) = seen.insert(self.state(), (self.rocks, self.height())) {
+                    let (cycle, gain) = (self.rocks - r, self.height() - h);
+                    let repeats = (rocks - self.rocks) / cycle;
+                    self.rocks += repeats * cycle;
+                    skipped = Some(repeats * gain);
+                    continue;
+                }
+            }
+            self.drop_rock();
+        }
+        self.height() + skipped.unwrap_or(0)
+    }
+
+    // Top rows of the tower like in the puzzle, with the floor once it's in view
+    pub fn render(&self, rows: usize) -> String {
+        let mut lines: Vec<String> = self
+            .rows
+            .iter()
+            .rev()
+            .take(rows)
+            .map(|row| {
+                let cells: String = (0..self.width)
+                    .map(|x| if row & (1 << x) != 0 { '#' } else { '.' })
+                    .collect();
+                format!("|{cells}|")
+            })
+            .collect();
+        if rows > self.rows.len() {
+            lines.push(format!("+{}+", "-".repeat(self.width)));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use nom::Finish;
+
+    fn jets(input: &str) -> Vec<Direction> {
+        parse(input).finish().expect("Parse error").1
+    }
+
+    #[test]
+    fn test_render() {
+        let pieces = parse_pieces(PIECES).unwrap();
+        assert_eq!(pieces.width, 7);
+        let jets = jets(include_str!("../test_input.txt"));
+        let mut chamber = Chamber::new(pieces.width, pieces.shapes, jets).unwrap();
+        for _ in 0..10 {
+            chamber.drop_rock();
+        }
+        assert_eq!(chamber.height(), 17);
+        assert_eq!(
+            chamber.render(20),
+            [
+                "|....#..|",
+                "|....#..|",
+                "|....##.|",
+                "|##..##.|",
+                "|######.|",
+                "|.###...|",
+                "|..#....|",
+                "|.####..|",
+                "|....##.|",
+                "|....##.|",
+                "|....#..|",
+                "|..#.#..|",
+                "|..#.#..|",
+                "|#####..|",
+                "|..###..|",
+                "|...#...|",
+                "|..####.|",
+                "+-------+",
+            ]
+            .join("\n")
+        );
+        assert_eq!(chamber.render(2), "|....#..|\n|....#..|");
+    }
+    #[test]
+    fn test_custom_pieces() {
+        let pieces = parse_pieces("width 5\n..##\n...#\n\n\n#.#\n###\n").unwrap();
+        assert_eq!(pieces.width, 5);
+        let pieces = pieces.shapes;
+        assert_eq!(
+            pieces,
+            [
+                Shape {
+                    rows: vec![0b10, 0b11],
+                    width: 2
+                },
+                Shape {
+                    rows: vec![0b111, 0b101],
+                    width: 3
+                },
+            ]
+        );
+        let jets = jets("<<>><>>><");
+        // Skipping cycles gives the same heights as dropping every rock
+        for width in [5, 6, 9] {
+            let empty = Chamber::new(width, pieces.clone(), jets.clone()).unwrap();
+            let mut chamber = empty.clone();
+            for rocks in 0..300 {
+                assert_eq!(empty.clone().height_after(rocks), chamber.height());
+                chamber.drop_rock();
+            }
+        }
+    }
+    #[test]
+    fn test_one_sided_jets() {
+        // Everything piles up on the left, the right columns stay open all the way down
+        let pieces = parse_pieces(PIECES).unwrap();
+        let chamber = Chamber::new(7, pieces.shapes, jets("<")).unwrap();
+        let mut brute = chamber.clone();
+        let mut heights = vec![0];
+        for _ in 0..1000 {
+            brute.drop_rock();
+            heights.push(brute.height());
+        }
+        for rocks in [0, 1, 7, 500, 999, 1000] {
+            assert_eq!(chamber.clone().height_after(rocks), heights[rocks]);
+        }
+        let gain = heights[1000] - heights[995];
+        assert_eq!(
+            chamber.height_after(1_000_000_000_000),
+            heights[1000] + (1_000_000_000_000 - 1000) / 5 * gain
+        );
+    }
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse_pieces("\n\n"), Err(ChamberError::NoPieces));
+        assert_eq!(
+            parse_pieces("##\n\n#x"),
+            Err(ChamberError::InvalidCell {
+                piece: 1,
+                cell: 'x'
+            })
+        );
+        assert_eq!(
+            parse_pieces(&format!("{}#", ".".repeat(64))),
+            Err(ChamberError::ColumnLimit { piece: 0 })
+        );
+        assert_eq!(
+            parse_pieces("##\n\n...\n..."),
+            Err(ChamberError::EmptyPiece { piece: 1 })
+        );
+        assert_eq!(
+            parse_pieces("width seven\n##"),
+            Err(ChamberError::InvalidWidth("width seven".into()))
+        );
+        assert_eq!(parse_pieces("##").unwrap().width, DEFAULT_WIDTH);
+        let empty = Shape {
+            rows: Vec::new(),
+            width: 0,
+        };
+        assert_eq!(
+            Chamber::new(7, vec![empty], jets(">")).err(),
+            Some(ChamberError::EmptyPiece { piece: 0 })
+        );
+        let pieces = parse_pieces(PIECES).unwrap().shapes;
+        let jets = jets(">");
+        assert_eq!(
+            Chamber::new(5, pieces.clone(), jets.clone()).err(),
+            Some(ChamberError::TooWide { piece: 0 })
+        );
+        assert_eq!(
+            Chamber::new(65, pieces.clone(), jets).err(),
+            Some(ChamberError::Width(65))
+        );
+        assert_eq!(
+            Chamber::new(7, pieces, Vec::new()).err(),
+            Some(ChamberError::NoJets)
+        );
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+pub type Coord = i32;
+pub type Voxel = (Coord, Coord, Coord);
+
+pub fn neighbors6(&(x, y, z): &Voxel) -> [Voxel; 6] {
+    [
+        (x - 1, y, z),
+        (x + 1, y, z),
+        (x, y - 1, z),
+        (x, y + 1, z),
+        (x, y, z - 1),
+        (x, y, z + 1),
+    ]
+}
+
+#[allow(dead_code)]
+pub fn neighbors26(&(x, y, z): &Voxel) -> impl Iterator<Item = Voxel> {
+    (-1..=1)
+        .flat_map(|dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| (dx, dy, dz))))
+        .filter(|&d| d != (0, 0, 0))
+        .map(move |(dx, dy, dz)| (x + dx, y + dy, z + dz))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min: Voxel,
+    pub max: Voxel,
+}
+
+impl BoundingBox {
+    pub fn of(voxels: impl IntoIterator<Item = Voxel>) -> Option<Self> {
+        voxels.into_iter().fold(None, |bounds, v| {
+            Some(match bounds {
+                None => BoundingBox { min: v, max: v },
+                Some(b) => b.including(v),
+            })
+        })
+    }
+    pub fn including(&self, (x, y, z): Voxel) -> Self {
+        let (x0, y0, z0) = self.min;
+        let (x1, y1, z1) = self.max;
+        BoundingBox {
+            min: (x0.min(x), y0.min(y), z0.min(z)),
+            max: (x1.max(x), y1.max(y), z1.max(z)),
+        }
+    }
+    pub fn grow(&self, n: Coord) -> Self {
+        let (x0, y0, z0) = self.min;
+        let (x1, y1, z1) = self.max;
+        BoundingBox {
+            min: (x0 - n, y0 - n, z0 - n),
+            max: (x1 + n, y1 + n, z1 + n),
+        }
+    }
+    pub fn contains(&self, (x, y, z): Voxel) -> bool {
+        let (x0, y0, z0) = self.min;
+        let (x1, y1, z1) = self.max;
+        (x0..=x1).contains(&x) && (y0..=y1).contains(&y) && (z0..=z1).contains(&z)
+    }
+    pub fn size(&self) -> (usize, usize, usize) {
+        let (x0, y0, z0) = self.min;
+        let (x1, y1, z1) = self.max;
+        (
+            (x1 - x0 + 1) as usize,
+            (y1 - y0 + 1) as usize,
+            (z1 - z0 + 1) as usize,
+        )
+    }
+    pub fn volume(&self) -> usize {
+        let (w, h, d) = self.size();
+        w * h * d
+    }
+    pub fn voxels(&self) -> impl Iterator<Item = Voxel> {
+        let (x0, y0, z0) = self.min;
+        let (x1, y1, z1) = self.max;
+        (z0..=z1).flat_map(move |z| (y0..=y1).flat_map(move |y| (x0..=x1).map(move |x| (x, y, z))))
+    }
+    fn index(&self, (x, y, z): Voxel) -> usize {
+        let (w, h, _) = self.size();
+        let (x0, y0, z0) = self.min;
+        ((z - z0) as usize * h + (y - y0) as usize) * w + (x - x0) as usize
+    }
+}
+
+pub trait VoxelGrid {
+    fn contains(&self, voxel: Voxel) -> bool;
+    fn insert(&mut self, voxel: Voxel) -> bool;
+    fn voxels(&self) -> impl Iterator<Item = Voxel> + '_;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::of(self.voxels())
+    }
+    fn faces(&self) -> impl Iterator<Item = Face> + '_ {
+        self.voxels().flat_map(move |voxel| {
+            neighbors6(&voxel)
+                .into_iter()
+                .filter(|n| !self.contains(*n))
+                .map(move |n| Face::between(voxel, n))
+        })
+    }
+    fn surface_area(&self) -> usize {
+        self.faces().count()
+    }
+    // Air reachable from outside the bounding box, flood filled within the box grown by one
+    fn exterior(&self) -> DenseGrid {
+        let Some(bounds) = self.bounding_box() else {
+            return DenseGrid::new(BoundingBox {
+                min: (0, 0, 0),
+                max: (0, 0, 0),
+            });
+        };
+        let bounds = bounds.grow(1);
+        let mut air = DenseGrid::new(bounds);
+        let mut stack = vec![bounds.min];
+        air.insert(bounds.min);
+        while let Some(voxel) = stack.pop() {
+            for n in neighbors6(&voxel) {
+                if bounds.contains(n) && !self.contains(n) && air.insert(n) {
+                    stack.push(n);
+                }
+            }
+        }
+        air
+    }
+    fn exterior_faces(&self) -> impl Iterator<Item = Face> + '_ {
+        let air = self.exterior();
+        self.faces()
+            .filter(move |face| air.contains(face.outside()))
+    }
+    fn exterior_surface_area(&self) -> usize {
+        self.exterior_faces().count()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SparseGrid {
+    cells: HashSet<Voxel>,
+}
+
+impl FromIterator<Voxel> for SparseGrid {
+    fn from_iter<T: IntoIterator<Item = Voxel>>(iter: T) -> Self {
+        SparseGrid {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl VoxelGrid for SparseGrid {
+    fn contains(&self, voxel: Voxel) -> bool {
+        self.cells.contains(&voxel)
+    }
+    fn insert(&mut self, voxel: Voxel) -> bool {
+        self.cells.insert(voxel)
+    }
+    fn voxels(&self) -> impl Iterator<Item = Voxel> + '_ {
+        self.cells.iter().copied()
+    }
+    fn len(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DenseGrid {
+    bounds: BoundingBox,
+    cells: Vec<bool>,
+    len: usize,
+}
+
+impl DenseGrid {
+    pub fn new(bounds: BoundingBox) -> Self {
+        DenseGrid {
+            bounds,
+            cells: vec![false; bounds.volume()],
+            len: 0,
+        }
+    }
+    fn resize(&mut self, bounds: BoundingBox) {
+        let mut grid = DenseGrid::new(bounds);
+        for voxel in self.voxels() {
+            grid.insert(voxel);
+        }
+        *self = grid;
+    }
+}
+
+impl FromIterator<Voxel> for DenseGrid {
+    fn from_iter<T: IntoIterator<Item = Voxel>>(iter: T) -> Self {
+        let voxels: Vec<_> = iter.into_iter().collect();
+        let bounds = BoundingBox::of(voxels.iter().copied()).unwrap_or(BoundingBox {
+            min: (0, 0, 0),
+            max: (0, 0, 0),
+        });
+        let mut grid = DenseGrid::new(bounds);
+        for voxel in voxels {
+            grid.insert(voxel);
+        }
+        grid
+    }
+}
+
+impl VoxelGrid for DenseGrid {
+    fn contains(&self, voxel: Voxel) -> bool {
+        self.bounds.contains(voxel) && self.cells[self.bounds.index(voxel)]
+    }
+    fn insert(&mut self, voxel: Voxel) -> bool {
+        if !self.bounds.contains(voxel) {
+            self.resize(self.bounds.including(voxel));
+        }
+        let i = self.bounds.index(voxel);
+        let inserted = !self.cells[i];
+        self.cells[i] = true;
+        self.len += inserted as usize;
+        inserted
+    }
+    fn voxels(&self) -> impl Iterator<Item = Voxel> + '_ {
+        self.bounds
+            .voxels()
+            .zip(self.cells.iter())
+            .filter_map(|(voxel, &set)| set.then_some(voxel))
+    }
+    fn len(&self) -> usize {
+        self.len
+    }
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        if self.is_empty() {
+            None
+        } else {
+            BoundingBox::of(self.voxels())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Face {
+    pub voxel: Voxel,
+    pub normal: Voxel,
+}
+
+impl Face {
+    fn between(voxel @ (x0, y0, z0): Voxel, (x1, y1, z1): Voxel) -> Self {
+        Face {
+            voxel,
+            normal: (x1 - x0, y1 - y0, z1 - z0),
+        }
+    }
+    pub fn outside(&self) -> Voxel {
+        let (x, y, z) = self.voxel;
+        let (nx, ny, nz) = self.normal;
+        (x + nx, y + ny, z + nz)
+    }
+    // Corners in counter-clockwise order when looking at the face from outside
+    pub fn corners(&self) -> [Voxel; 4] {
+        let (x, y, z) = self.voxel;
+        let normal = [self.normal.0, self.normal.1, self.normal.2];
+        let axis = normal.iter().position(|&n| n != 0).expect("Zero normal");
+        let (u, w) = ((axis + 1) % 3, (axis + 2) % 3);
+        let offsets = [(0, 0), (1, 0), (1, 1), (0, 1)].map(|(du, dw)| {
+            let mut corner = [x, y, z];
+            corner[axis] += (normal[axis] > 0) as Coord;
+            corner[u] += du;
+            corner[w] += dw;
+            (corner[0], corner[1], corner[2])
+        });
+        if normal[axis] > 0 {
+            offsets
+        } else {
+            let [a, b, c, d] = offsets;
+            [a, d, c, b]
+        }
+    }
+}
+
+pub fn to_obj(faces: impl IntoIterator<Item = Face>) -> String {
+    let mut vertices: HashMap<Voxel, usize> = HashMap::new();
+    let mut vertex_lines = String::new();
+    let mut face_lines = String::new();
+    for face in faces {
+        let indices = face.corners().map(|corner| {
+            let next = vertices.len() + 1;
+            *vertices.entry(corner).or_insert_with(|| {
+                let (x, y, z) = corner;
+                writeln!(vertex_lines, "v {x} {y} {z}").unwrap();
+                next
+            })
+        });
+        let [a, b, c, d] = indices;
+        writeln!(face_lines, "f {a} {b} {c} {d}").unwrap();
+    }
+    vertex_lines + &face_lines
+}
+
+pub fn to_stl(name: &str, faces: impl IntoIterator<Item = Face>) -> String {
+    let mut out = format!("solid {name}\n");
+    for face in faces {
+        let (nx, ny, nz) = face.normal;
+        let [a, b, c, d] = face.corners();
+        for triangle in [[a, b, c], [a, c, d]] {
+            writeln!(out, "facet normal {nx} {ny} {nz}").unwrap();
+            writeln!(out, "  outer loop").unwrap();
+            for (x, y, z) in triangle {
+                writeln!(out, "    vertex {x} {y} {z}").unwrap();
+            }
+            writeln!(out, "  endloop").unwrap();
+            writeln!(out, "endfacet").unwrap();
+        }
+    }
+    writeln!(out, "endsolid {name}").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hollow_cube() -> Vec<Voxel> {
+        BoundingBox {
+            min: (0, 0, 0),
+            max: (2, 2, 2),
+        }
+        .voxels()
+        .filter(|&v| v != (1, 1, 1))
+        .collect()
+    }
+
+    #[test]
+    fn test_neighbors26() {
+        let n: HashSet<_> = neighbors26(&(0, 0, 0)).collect();
+        assert_eq!(n.len(), 26);
+        assert!(!n.contains(&(0, 0, 0)));
+        assert!(n.contains(&(1, -1, 1)));
+    }
+    #[test]
+    fn test_dense_matches_sparse() {
+        let sparse: SparseGrid = hollow_cube().into_iter().collect();
+        let dense: DenseGrid = hollow_cube().into_iter().collect();
+        assert_eq!(sparse.len(), 26);
+        assert_eq!(dense.len(), 26);
+        assert_eq!(sparse.bounding_box(), dense.bounding_box());
+        assert_eq!(sparse.surface_area(), 54 + 6);
+        assert_eq!(dense.surface_area(), 54 + 6);
+        assert_eq!(sparse.exterior_surface_area(), 54);
+        assert_eq!(dense.exterior_surface_area(), 54);
+    }
+    #[test]
+    fn test_dense_grows() {
+        let mut grid = DenseGrid::new(BoundingBox {
+            min: (0, 0, 0),
+            max: (0, 0, 0),
+        });
+        assert!(grid.insert((0, 0, 0)));
+        assert!(grid.insert((-2, 3, 1)));
+        assert!(!grid.insert((-2, 3, 1)));
+        assert_eq!(grid.len(), 2);
+        assert!(grid.contains((0, 0, 0)));
+        assert!(grid.contains((-2, 3, 1)));
+        assert_eq!(
+            grid.bounding_box(),
+            Some(BoundingBox {
+                min: (-2, 0, 0),
+                max: (0, 3, 1)
+            })
+        );
+    }
+    #[test]
+    fn test_face_winding() {
+        let grid: SparseGrid = [(0, 0, 0)].into_iter().collect();
+        for face in grid.faces() {
+            let [a, b, c, _] = face.corners();
+            let e1 = (b.0 - a.0, b.1 - a.1, b.2 - a.2);
+            let e2 = (c.0 - a.0, c.1 - a.1, c.2 - a.2);
+            let cross = (
+                e1.1 * e2.2 - e1.2 * e2.1,
+                e1.2 * e2.0 - e1.0 * e2.2,
+                e1.0 * e2.1 - e1.1 * e2.0,
+            );
+            assert_eq!(cross, face.normal);
+        }
+    }
+    #[test]
+    fn test_mesh_export() {
+        let grid: SparseGrid = [(0, 0, 0)].into_iter().collect();
+        let obj = to_obj(grid.faces());
+        assert_eq!(obj.lines().filter(|l| l.starts_with("v ")).count(), 8);
+        assert_eq!(obj.lines().filter(|l| l.starts_with("f ")).count(), 6);
+        let stl = to_stl("cube", grid.faces());
+        assert!(stl.starts_with("solid cube\n"));
+        assert!(stl.ends_with("endsolid cube\n"));
+        assert_eq!(stl.matches("facet normal").count(), 12);
+    }
+}
@@ -32,21 +32,50 @@ impl std::ops::Add<Resources> for Resources {
     }
 }
 impl Resources {
-    fn covers(&self, r: &Resources) -> bool {
-       self.ore >= r.ore
-           && self.clay >= r.clay
-           && self.obsidian >= r.obsidian
-           && self.geode >= r.geode 
-    }
-    fn consume(&self, r: &Resources) -> Option<Resources> {
-        self.covers(r).then_some(Resources {
+    fn get(&self, robot: Robot) -> Amount {
+        match robot {
+            Robot::Ore => self.ore,
+            Robot::Clay => self.clay,
+            Robot::Obsidian => self.obsidian,
+            Robot::Geode => self.geode,
+        }
+    }
+    fn get_mut(&mut self, robot: Robot) -> &mut Amount {
+        match robot {
+            Robot::Ore => &mut self.ore,
+            Robot::Clay => &mut self.clay,
+            Robot::Obsidian => &mut self.obsidian,
+            Robot::Geode => &mut self.geode,
+        }
+    }
+    fn scaled(&self, n: Amount) -> Resources {
+        Resources {
+            ore: self.ore * n,
+            clay: self.clay * n,
+            obsidian: self.obsidian * n,
+            geode: self.geode * n
+        }
+    }
+    fn consume(&self, r: &Resources) -> Resources {
+        Resources {
             ore: self.ore - r.ore,
             clay: self.clay - r.clay,
             obsidian: self.obsidian - r.obsidian,
             geode: self.geode - r.geode
-        })
+        }
     }
 }
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Robot {
+    Ore,
+    Clay,
+    Obsidian,
+    Geode
+}
+// Most valuable first so good solutions are found early and prune more
+const ROBOTS: [Robot; 4] = [Robot::Geode, Robot::Obsidian, Robot::Clay, Robot::Ore];
+
 type Id = u32;
 #[derive(Debug)]
 struct Blueprint {
@@ -56,6 +85,26 @@ struct Blueprint {
     obsidian: Resources,
     geode: Resources
 }
+impl Blueprint {
+    fn cost(&self, robot: Robot) -> &Resources {
+        match robot {
+            Robot::Ore => &self.ore,
+            Robot::Clay => &self.clay,
+            Robot::Obsidian => &self.obsidian,
+            Robot::Geode => &self.geode,
+        }
+    }
+    // No point having more robots of a kind than can be spent in one minute
+    fn max_spend(&self) -> Resources {
+        let costs = [self.ore, self.clay, self.obsidian, self.geode];
+        Resources {
+            ore: costs.iter().map(|c| c.ore).max().unwrap(),
+            clay: costs.iter().map(|c| c.clay).max().unwrap(),
+            obsidian: costs.iter().map(|c| c.obsidian).max().unwrap(),
+            geode: Amount::MAX
+        }
+    }
+}
 fn parse(input: &str) -> nom::IResult<&str, Vec<Blueprint>> {
     fn cost(i: &str) -> nom::IResult<&str, Resources> {
         let (rest, ore) = opt(terminated(ncc::u32, tag(" ore")))(i)?;
@@ -77,59 +126,113 @@ fn parse(input: &str) -> nom::IResult<&str, Vec<Blueprint>> {
     all_consuming(delimited(ncc::multispace0, separated_list1(ncc::line_ending, blueprint), ncc::multispace0))(input)
 }
 
-fn blueprint_geodes(blueprint: &Blueprint, time: u32) -> u32 {
-    println!("{blueprint:?}");
-    let resources = Resources::default();
-    let mut robots = Resources::default();
-    robots.ore = 1;
+// Minute of the build and the robot built
+type Build = (u32, Robot);
 
-    let best = std::sync::atomic::AtomicU32::new(0);
-    let (sender, receiver) = crossbeam::channel::unbounded();
-    sender.send((0, resources, robots)).unwrap();
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Outcome {
+    geodes: Amount,
+    builds: Vec<Build>
+}
 
-    receiver.iter().par_bridge().for_each(|(t, resources, robots)| {
-        let best_local = best.fetch_update(std::sync::atomic::Ordering::SeqCst, std::sync::atomic::Ordering::SeqCst, |val| Some(val.max(resources.geode))).unwrap();
-        let time_left = time - t;
-        if time_left == 0 || resources.geode + (robots.geode + time_left) * time_left < best_local {
-            return;
-        }
+struct Search<'a> {
+    blueprint: &'a Blueprint,
+    time: u32,
+    max_spend: Resources,
+    builds: Vec<Build>,
+    best: Outcome
+}
 
-        sender.send((t + 1, resources + robots, robots)).unwrap();
-        if let Some(resources) = resources.consume(&blueprint.ore) {
-            let mut rs = robots;
-            rs.ore += 1;
-            sender.send((t + 1, resources + robots, rs)).unwrap();
+impl Search<'_> {
+    // Optimistic estimate: ignore ore and clay, get a free obsidian robot every minute and
+    // a geode robot whenever there's enough obsidian
+    fn upper_bound(&self, time_left: u32, resources: &Resources, robots: &Resources) -> Amount {
+        let cost = self.blueprint.geode.obsidian;
+        let (mut obsidian, mut obsidian_robots) = (resources.obsidian, robots.obsidian);
+        let (mut geodes, mut geode_robots) = (resources.geode, robots.geode);
+        for _ in 0..time_left {
+            let build = obsidian >= cost;
+            if build {
+                obsidian -= cost;
+            }
+            obsidian += obsidian_robots;
+            geodes += geode_robots;
+            obsidian_robots += 1;
+            geode_robots += build as Amount;
         }
-        if let Some(resources) = resources.consume(&blueprint.clay) {
-            let mut rs = robots;
-            rs.clay += 1;
-            sender.send((t + 1, resources + robots, rs)).unwrap();
+        geodes
+    }
+
+    fn explore(&mut self, t: u32, resources: Resources, robots: Resources) {
+        let time_left = self.time - t;
+        let idle = resources.geode + robots.geode * time_left;
+        if idle > self.best.geodes {
+            self.best = Outcome { geodes: idle, builds: self.builds.clone() };
         }
-        if let Some(resources) = resources.consume(&blueprint.obsidian) {
-            let mut rs = robots;
-            rs.obsidian += 1;
-            sender.send((t + 1, resources + robots, rs)).unwrap();
+        if self.upper_bound(time_left, &resources, &robots) <= self.best.geodes {
+            return;
         }
-        if let Some(resources) = resources.consume(&blueprint.geode) {
-            let mut rs = robots;
-            rs.geode += 1;
-            sender.send((t + 1, resources + robots, rs)).unwrap();
+        for robot in ROBOTS {
+            if robots.get(robot) >= self.max_spend.get(robot) {
+                continue;
+            }
+            // Skip ahead to the minute the robot can be afforded
+            let cost = self.blueprint.cost(robot);
+            let wait = [Robot::Ore, Robot::Clay, Robot::Obsidian].iter().try_fold(0, |wait: u32, &r| {
+                let missing = cost.get(r).saturating_sub(resources.get(r));
+                match (missing, robots.get(r)) {
+                    (0, _) => Some(wait),
+                    (_, 0) => None,
+                    (m, n) => Some(wait.max(m.div_ceil(n)))
+                }
+            });
+            let Some(wait) = wait else { continue };
+            // A robot built in the last minute can't collect anything
+            if wait + 1 >= time_left {
+                continue;
+            }
+            let mut next_robots = robots;
+            *next_robots.get_mut(robot) += 1;
+            self.builds.push((t + wait + 1, robot));
+            self.explore(t + wait + 1, (resources + robots.scaled(wait + 1)).consume(cost), next_robots);
+            self.builds.pop();
         }
-    });
+    }
+}
 
-    best.load(std::sync::atomic::Ordering::SeqCst)
+fn blueprint_geodes(blueprint: &Blueprint, time: u32) -> Outcome {
+    let mut search = Search {
+        blueprint,
+        time,
+        max_spend: blueprint.max_spend(),
+        builds: Vec::new(),
+        best: Outcome::default()
+    };
+    search.explore(0, Resources::default(), Resources { ore: 1, ..Default::default() });
+    search.best
 }
-fn part1(blueprints: &Vec<Blueprint>) -> u32 {
-    blueprints.par_iter().map(|bp| bp.id * blueprint_geodes(bp, 24)).sum()
+
+fn part1(blueprints: &[Blueprint]) -> u32 {
+    blueprints.par_iter().map(|bp| bp.id * blueprint_geodes(bp, 24).geodes).sum()
 }
 
-fn part2(blueprints: &Vec<Blueprint>) -> u32 {
-    blueprints.par_iter().take(3).map(|bp| blueprint_geodes(bp, 32)).product()
+fn part2(blueprints: &[Blueprint]) -> u32 {
+    blueprints.par_iter().take(3).map(|bp| blueprint_geodes(bp, 32).geodes).product()
 }
 
 fn main() {
     let (_, blueprints) = parse(PUZZLE_INPUT).finish().expect("Parse error");
-    //println!("Part 1: {}", part1(&blueprints));
+    if std::env::args().nth(1).as_deref() == Some("explain") {
+        for bp in &blueprints {
+            let outcome = blueprint_geodes(bp, 24);
+            println!("Blueprint {}: {} geodes", bp.id, outcome.geodes);
+            for (minute, robot) in outcome.builds {
+                println!("  minute {minute:>2}: {robot:?} robot");
+            }
+        }
+        return;
+    }
+    println!("Part 1: {}", part1(&blueprints));
     println!("Part 2: {}", part2(&blueprints));
 }
 
@@ -147,21 +250,43 @@ mod test {
         let (_, blueprints) = parse(PUZZLE_INPUT).finish().expect("Parse error");
         assert_eq!(part1(&blueprints), 1356);
     }
-    /*
     #[test]
     fn test_part2() {
-        let (_, moves) = parse(PUZZLE_INPUT).finish().expect("Parse error");
-        assert_eq!(part2(&moves), 1_514_285_714_288);
+        let (_, blueprints) = parse(TEST_INPUT).finish().expect("Parse error");
+        assert_eq!(blueprint_geodes(&blueprints[0], 32).geodes, 56);
+        assert_eq!(blueprint_geodes(&blueprints[1], 32).geodes, 62);
     }
-
     #[test]
     fn test_part2_puzzle() {
-        let (_, sensors) = parse(PUZZLE_INPUT).finish().expect("Parse error");
-        assert_eq!(part2(&sensors, 0, 4_000_000), Some(10649103160102));
+        let (_, blueprints) = parse(PUZZLE_INPUT).finish().expect("Parse error");
+        assert_eq!(part2(&blueprints), 27720);
     }
-    */
-}
-
-
-
+    #[test]
+    fn test_build_order() {
+        let (_, blueprints) = parse(TEST_INPUT).finish().expect("Parse error");
+        let outcome = blueprint_geodes(&blueprints[0], 24);
+        assert_eq!(outcome.geodes, 9);
+        assert_eq!(outcome, blueprint_geodes(&blueprints[0], 24));
 
+        // Replay the build order and check it is affordable and yields the same geodes
+        let bp = &blueprints[0];
+        let mut resources = Resources::default();
+        let mut robots = Resources { ore: 1, ..Default::default() };
+        let mut builds = outcome.builds.iter().peekable();
+        for minute in 1..=24 {
+            let built = builds.next_if(|(m, _)| *m == minute).map(|&(_, robot)| {
+                let cost = bp.cost(robot);
+                let affordable = ROBOTS.iter().all(|&r| resources.get(r) >= cost.get(r));
+                assert!(affordable, "Can't afford {robot:?} on minute {minute}");
+                resources = resources.consume(cost);
+                robot
+            });
+            resources = resources + robots;
+            if let Some(robot) = built {
+                *robots.get_mut(robot) += 1;
+            }
+        }
+        assert!(builds.next().is_none());
+        assert_eq!(resources.geode, 9);
+    }
+}
@@ -6,6 +6,10 @@ use nom::{
     Finish,
 };
 
+use treap::CircularList;
+
+mod treap;
+
 const PUZZLE_INPUT: &str = include_str!("../puzzle_input.txt");
 
 type Numbers = Vec<i64>;
@@ -13,7 +17,7 @@ fn parse(input: &str) -> nom::IResult<&str, Numbers> {
     all_consuming(delimited(ncc::multispace0, separated_list1(ncc::line_ending, ncc::i64), ncc::multispace0))(input)
 }
 
-fn rotate<T>(numbers: &mut Vec<T>, at: usize, delta: i64) {
+fn rotate<T>(numbers: &mut [T], at: usize, delta: i64) {
     let n = numbers.len() as i64;
     // determine preceding index in array without item to rotate
     let after = if at == 0 { n - 2 } else { at as i64 - 1 };
@@ -49,38 +53,52 @@ fn rotate<T>(numbers: &mut Vec<T>, at: usize, delta: i64) {
     }
 }
 
-fn part1(numbers: &Numbers) -> i64 {
-    let mut buffer: Vec<_> = (0..numbers.len()).collect();
-    for i in 0..numbers.len() {
-        let at = buffer.iter().enumerate().find_map(|(k, j)| (i == *j).then_some(k)).unwrap();
-        rotate(&mut buffer, at, numbers[i]);
+fn mix(numbers: &Numbers, rounds: usize) -> Numbers {
+    let mut list = CircularList::new(numbers.len());
+    for _ in 0..rounds {
+        for (i, n) in numbers.iter().enumerate() {
+            list.move_by(i, *n);
+        }
     }
-    let result: Vec<_> = buffer.into_iter().map(|i| numbers[i]).collect();
-    let zero = result.iter().enumerate().find_map(|(i, n)| (*n == 0).then_some(i)).unwrap();
-    
-    result.iter().cycle().skip(zero).step_by(1000).skip(1).take(3).sum()
+    (0..list.len()).map(|i| numbers[list.at(i)]).collect()
 }
 
-fn part2(numbers: &Numbers) -> i64 {
-    const KEY: i64 = 811589153;
-    let numbers: Vec<_> = numbers.iter().map(|n| n * KEY).collect();
+// The original O(n) per move version, kept for reference
+fn mix_reference(numbers: &Numbers, rounds: usize) -> Numbers {
     let mut buffer: Vec<_> = (0..numbers.len()).collect();
-    for _ in 0..10 {
-        for i in 0..numbers.len() {
-            let at = buffer.iter().enumerate().find_map(|(k, j)| (i == *j).then_some(k)).unwrap();
-            rotate(&mut buffer, at, numbers[i]);
+    for _ in 0..rounds {
+        for (i, &n) in numbers.iter().enumerate() {
+            let at = buffer.iter().position(|j| i == *j).unwrap();
+            rotate(&mut buffer, at, n);
         }
     }
-    let result: Vec<_> = buffer.into_iter().map(|i| numbers[i]).collect();
-    let zero = result.iter().enumerate().find_map(|(i, n)| (*n == 0).then_some(i)).unwrap();
-    
+    buffer.into_iter().map(|i| numbers[i]).collect()
+}
+
+fn grove_coordinates(result: &Numbers) -> i64 {
+    let zero = result.iter().position(|n| *n == 0).unwrap();
     result.iter().cycle().skip(zero).step_by(1000).skip(1).take(3).sum()
 }
 
+fn part1(numbers: &Numbers, mix: fn(&Numbers, usize) -> Numbers) -> i64 {
+    grove_coordinates(&mix(numbers, 1))
+}
+
+fn part2(numbers: &Numbers, mix: fn(&Numbers, usize) -> Numbers) -> i64 {
+    const KEY: i64 = 811589153;
+    let numbers: Vec<_> = numbers.iter().map(|n| n * KEY).collect();
+    grove_coordinates(&mix(&numbers, 10))
+}
+
 fn main() {
     let (_, numbers) = parse(PUZZLE_INPUT).finish().expect("Parse error");
-    println!("Part 1: {}", part1(&numbers));
-    println!("Part 2: {}", part2(&numbers));
+    let mix = if std::env::args().nth(1).as_deref() == Some("reference") {
+        mix_reference
+    } else {
+        mix
+    };
+    println!("Part 1: {}", part1(&numbers, mix));
+    println!("Part 2: {}", part2(&numbers, mix));
 }
 
 #[cfg(test)]
@@ -112,25 +130,52 @@ mod test {
         assert_eq!(numbers, vec![2, 1, 3]);
     }
     #[test]
+    fn test_move_by() {
+        let mut list = CircularList::new(7);
+        let order = |list: &CircularList| (0..list.len()).map(|i| list.at(i)).collect::<Vec<_>>();
+        list.move_by(0, 3);
+        assert_eq!(order(&list), [1, 2, 3, 0, 4, 5, 6]);
+        assert_eq!(list.index_of(0), 3);
+        assert_eq!(list.at(3), 0);
+        assert_eq!(list.at(10), 0);
+        list.move_by(6, -8);
+        assert_eq!(order(&list), [1, 2, 3, 0, 6, 4, 5]);
+        assert_eq!((0..7).map(|i| list.index_of(list.at(i))).collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5, 6]);
+    }
+    #[test]
+    fn test_mix_matches_reference() {
+        let (_, numbers) = parse(TEST_INPUT).finish().expect("Parse error");
+        assert_eq!(mix(&numbers, 1), mix_reference(&numbers, 1));
+        assert_eq!(mix(&numbers, 1), [1, 2, -3, 4, 0, 3, -2]);
+        let mut seed = 12345_i64;
+        for len in 2..40 {
+            let numbers: Numbers = (0..len).map(|_| {
+                seed = (seed * 1103515245 + 12345) % (1 << 31);
+                seed % 201 - 100
+            }).collect();
+            assert_eq!(mix(&numbers, 3), mix_reference(&numbers, 3), "{numbers:?}");
+        }
+    }
+    #[test]
     fn test_part1() {
         let (_, numbers) = parse(TEST_INPUT).finish().expect("Parse error");
-        assert_eq!(part1(&numbers), 3);
+        assert_eq!(part1(&numbers, mix), 3);
     }
     #[test]
     fn test_part1_puzzle() {
         let (_, numbers) = parse(PUZZLE_INPUT).finish().expect("Parse error");
-        assert_eq!(part1(&numbers), 2215);
+        assert_eq!(part1(&numbers, mix), 2215);
     }
     #[test]
     fn test_part2() {
         let (_, numbers) = parse(TEST_INPUT).finish().expect("Parse error");
-        assert_eq!(part2(&numbers), 1623178306);
+        assert_eq!(part2(&numbers, mix), 1623178306);
     }
 
     #[test]
     fn test_part2_puzzle() {
         let (_, numbers) = parse(PUZZLE_INPUT).finish().expect("Parse error");
-        assert_eq!(part2(&numbers), 8927480683);
+        assert_eq!(part2(&numbers, mix), 8927480683);
     }
 }
 
@@ -0,0 +1,170 @@
+// Implicit treap over item ids 0..n. Position in the sequence is given by subtree sizes,
+// and parent links allow finding the position of an item by id in O(log n).
+
+const NIL: usize = usize::MAX;
+
+#[derive(Clone, Debug)]
+struct Node {
+    left: usize,
+    right: usize,
+    parent: usize,
+    size: usize,
+    priority: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct CircularList {
+    nodes: Vec<Node>,
+    root: usize,
+}
+
+impl CircularList {
+    pub fn new(len: usize) -> Self {
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let nodes = (0..len)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                Node {
+                    left: NIL,
+                    right: NIL,
+                    parent: NIL,
+                    size: 1,
+                    priority: seed,
+                }
+            })
+            .collect();
+        let mut list = CircularList { nodes, root: NIL };
+        for id in 0..len {
+            list.root = list.merge(list.root, id);
+        }
+        list
+    }
+
+    pub fn len(&self) -> usize {
+        self.size(self.root)
+    }
+
+    fn size(&self, t: usize) -> usize {
+        if t == NIL {
+            0
+        } else {
+            self.nodes[t].size
+        }
+    }
+
+    fn set_parent(&mut self, t: usize, parent: usize) {
+        if t != NIL {
+            self.nodes[t].parent = parent;
+        }
+    }
+
+    fn update(&mut self, t: usize) {
+        let (l, r) = (self.nodes[t].left, self.nodes[t].right);
+        self.nodes[t].size = 1 + self.size(l) + self.size(r);
+        self.set_parent(l, t);
+        self.set_parent(r, t);
+    }
+
+    fn merge(&mut self, a: usize, b: usize) -> usize {
+        if a == NIL || b == NIL {
+            let t = if a == NIL { b } else { a };
+            self.set_parent(t, NIL);
+            return t;
+        }
+        if self.nodes[a].priority > self.nodes[b].priority {
+            let r = self.merge(self.nodes[a].right, b);
+            self.nodes[a].right = r;
+            self.update(a);
+            self.nodes[a].parent = NIL;
+            a
+        } else {
+            let l = self.merge(a, self.nodes[b].left);
+            self.nodes[b].left = l;
+            self.update(b);
+            self.nodes[b].parent = NIL;
+            b
+        }
+    }
+
+    // First `k` items go left
+    fn split(&mut self, t: usize, k: usize) -> (usize, usize) {
+        if t == NIL {
+            return (NIL, NIL);
+        }
+        let left_size = self.size(self.nodes[t].left);
+        if k <= left_size {
+            let (l, r) = self.split(self.nodes[t].left, k);
+            self.nodes[t].left = r;
+            self.update(t);
+            self.set_parent(l, NIL);
+            self.nodes[t].parent = NIL;
+            (l, t)
+        } else {
+            let (l, r) = self.split(self.nodes[t].right, k - left_size - 1);
+            self.nodes[t].right = l;
+            self.update(t);
+            self.set_parent(r, NIL);
+            self.nodes[t].parent = NIL;
+            (t, r)
+        }
+    }
+
+    pub fn index_of(&self, id: usize) -> usize {
+        let mut index = self.size(self.nodes[id].left);
+        let mut t = id;
+        while self.nodes[t].parent != NIL {
+            let parent = self.nodes[t].parent;
+            if self.nodes[parent].right == t {
+                index += self.size(self.nodes[parent].left) + 1;
+            }
+            t = parent;
+        }
+        index
+    }
+
+    pub fn at(&self, index: usize) -> usize {
+        let mut index = index % self.len();
+        let mut t = self.root;
+        loop {
+            let left_size = self.size(self.nodes[t].left);
+            if index < left_size {
+                t = self.nodes[t].left;
+            } else if index == left_size {
+                return t;
+            } else {
+                index -= left_size + 1;
+                t = self.nodes[t].right;
+            }
+        }
+    }
+
+    fn remove_at(&mut self, index: usize) -> usize {
+        let (l, r) = self.split(self.root, index);
+        let (item, r) = self.split(r, 1);
+        self.root = self.merge(l, r);
+        item
+    }
+
+    fn insert_at(&mut self, index: usize, id: usize) {
+        let (l, r) = self.split(self.root, index);
+        let l = self.merge(l, id);
+        self.root = self.merge(l, r);
+    }
+
+    // Moves the item `delta` steps around the circle of the other items, like `rotate`
+    pub fn move_by(&mut self, id: usize, delta: i64) {
+        let n = self.len() as i64;
+        if n < 2 || delta == 0 {
+            return;
+        }
+        let at = self.index_of(id) as i64;
+        let before = if at == 0 { n - 2 } else { at - 1 };
+        let to = (before + delta).rem_euclid(n - 1) + 1;
+        if to != at {
+            let item = self.remove_at(at as usize);
+            self.insert_at(to as usize, item);
+        }
+    }
+}
@@ -1,4 +1,6 @@
+use num::BigInt;
 use std::collections::HashMap;
+use symbolic::{Expr, Op, SolveError};
 use nom::{
     character::complete as ncc,
     multi::separated_list1,
@@ -8,6 +10,8 @@ use nom::{
     Finish, bytes::complete::tag,
 };
 
+mod symbolic;
+
 const PUZZLE_INPUT: &str = include_str!("../puzzle_input.txt");
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum Node<'a> {
@@ -16,7 +20,6 @@ enum Node<'a> {
     Sub(&'a str, &'a str),
     Mul(&'a str, &'a str),
     Div(&'a str, &'a str),
-    Human
 }
 type Data<'a> = Vec<(&'a str, Node<'a>)>;
 fn parse<'a>(input: &'a str) -> nom::IResult<&'a str, Data<'a>> {
@@ -33,131 +36,64 @@ fn parse<'a>(input: &'a str) -> nom::IResult<&'a str, Data<'a>> {
     all_consuming(delimited(ncc::multispace0, data, ncc::multispace0))(input)
 }
 
-fn eval<'a>(mut nodes: HashMap<&'a str, Node<'a>>, name: &'a str, human: Option<i64>) -> (Option<i64>, HashMap<&'a str, Node<'a>>) {
-    let mut stack: Vec<_> = [name].into();
-    use Node::*;
-    while let Some(name) = stack.pop() {
-        let node = *nodes.get(name).expect("Node not found");
-        match node {
-            Value(_) => continue,
-            Add(a, b) => {
-                if let (Some(Value(a)), Some(Value(b))) = (nodes.get(a), nodes.get(b)) {
-                    nodes.insert(name, Value(a + b));
-                } else {
-                    stack.push(name);
-                    stack.push(a);
-                    stack.push(b);
-                }
-            },
-            Sub(a, b) => {
-                if let (Some(Value(a)), Some(Value(b))) = (nodes.get(a), nodes.get(b)) {
-                    nodes.insert(name, Value(a - b));
-                } else {
-                    stack.push(name);
-                    stack.push(a);
-                    stack.push(b);
-                }
-            },
-            Mul(a, b) => {
-                if let (Some(Value(a)), Some(Value(b))) = (nodes.get(a), nodes.get(b)) {
-                    nodes.insert(name, Value(a * b));
-                } else {
-                    stack.push(name);
-                    stack.push(a);
-                    stack.push(b);
-                }
-            },
-            Div(a, b) => {
-                if let (Some(Value(a)), Some(Value(b))) = (nodes.get(a), nodes.get(b)) {
-                    nodes.insert(name, Value(a / b));
-                } else {
-                    stack.push(name);
-                    stack.push(a);
-                    stack.push(b);
-                }
-            },
-            Human => if let Some(value) = human {
-                nodes.insert(name, Value(value));
-            } else {
-                return (None, nodes);
-            }
-        }
-    }
-
-    if let Some(Value(result)) = nodes.get(name) {
-        return (Some(*result), nodes);
-    } else {
-        unreachable!()
+// Builds the expression for a monkey, with `unknown` left as a variable
+fn expression(nodes: &HashMap<&str, Node>, name: &str, unknown: Option<&str>) -> Result<Expr, SolveError> {
+    if Some(name) == unknown {
+        return Ok(Expr::Var(name.to_string()));
     }
+    let node = *nodes.get(name).expect("Node not found");
+    let (op, a, b) = match node {
+        Node::Value(v) => return Ok(Expr::constant(v)),
+        Node::Add(a, b) => (Op::Add, a, b),
+        Node::Sub(a, b) => (Op::Sub, a, b),
+        Node::Mul(a, b) => (Op::Mul, a, b),
+        Node::Div(a, b) => (Op::Div, a, b),
+    };
+    Expr::binary(op, expression(nodes, a, unknown)?, expression(nodes, b, unknown)?)
 }
 
 fn part1(data: &Data) -> i64 {
     let nodes: HashMap<_, _> = data.iter().copied().collect();
-    eval(nodes, "root", None).0.unwrap()
+    let value = expression(&nodes, "root", None).expect("Division by zero");
+    let value = value.as_const().expect("Root is not constant");
+    assert!(value.is_integer(), "Root is not an integer: {value}");
+    value.to_integer().try_into().expect("Root does not fit in i64")
 }
 
-fn determine_human<'a>(mut nodes: HashMap<&'a str, Node<'a>>, name: &'a str, result: i64) -> i64 {
-    use Node::*;
-    let node = *nodes.get(name).expect("Node not found");
-    println!("{name} = {node:?} should equal {result}");
-    let (value, human) = match node {
-        Value(_) => unreachable!(),
-        Add(a, b) | Sub(a, b) | Mul(a, b) | Div(a, b) => {
-            let va;
-            let vb;
-            (va, nodes) = eval(nodes, a, None);
-            (vb, nodes) = eval(nodes, b, None);
-            if let Some(value) = va {
-                println!("  {a} = {value}");
-                (value, b)
-            } else if let Some(value) = vb {
-                println!("  {b} = {value}");
-                (value, a)
-            } else {
-                unreachable!()
-            }
-        },
-        Human => return result
+// Solves the value of `unknown` that makes both sides of `root` equal
+fn solve_equality(data: &Data, unknown: &str, mut trace: Option<&mut Vec<String>>) -> Result<BigInt, SolveError> {
+    let nodes: HashMap<_, _> = data.iter().copied().collect();
+    let (a, b) = match *nodes.get("root").expect("No root") {
+        Node::Add(a, b) | Node::Sub(a, b) | Node::Mul(a, b) | Node::Div(a, b)  => (a, b),
+        Node::Value(_) => panic!("Root is not an operation")
     };
-    match node {
-        Add(_, _) => determine_human(nodes, human, result - value),
-        Sub(a, _) if a == human => determine_human(nodes, human, result + value),
-        Sub(_, b) if b == human => determine_human(nodes, human, value - result),
-        Mul(_, _) => determine_human(nodes, human, result / value),
-        Div(a, _) if a == human => determine_human(nodes, human, value * result),
-        Div(_, b) if b == human => determine_human(nodes, human, value / result),
-        _ => unreachable!()
+    let lhs = expression(&nodes, a, Some(unknown))?;
+    let rhs = expression(&nodes, b, Some(unknown))?;
+    if let Some(trace) = trace.as_deref_mut() {
+        trace.push(format!("{lhs} = {rhs}"));
     }
+    symbolic::solve(&lhs, &rhs, trace)
 }
-fn part2(data: &Data) -> i64 {
-    let mut nodes: HashMap<_, _> = data.iter().copied().collect();
-    nodes.insert("humn", Node::Human);
-    let root = *nodes.get("root").unwrap();
-    let (a, b) = match root {
-        Node::Add(a, b) | Node::Sub(a, b) | Node::Mul(a, b) | Node::Div(a, b)  => (a, b),
-        _ => unreachable!()
-    };
-    let va;
-    let vb;
-    (va, nodes) = eval(nodes, a, None);
-    (vb, nodes) = eval(nodes, b, None);
-    let (value, node) = {
-        if let Some(value) = va {
-            (value, b)
-        } else if let Some(value) = vb {
-            (value, a)
-        } else {
-            unreachable!()
-        }
-    };
 
-    determine_human(nodes, node, value)
+fn part2(data: &Data) -> i64 {
+    let human = solve_equality(data, "humn", None).expect("No solution");
+    human.try_into().expect("Solution does not fit in i64")
 }
 
 fn main() {
     let (_, data) = parse(PUZZLE_INPUT).finish().expect("Parse error");
     println!("Part 1: {}", part1(&data));
-    println!("Part 2: {}", part2(&data));
+    if std::env::args().nth(1).as_deref() == Some("trace") {
+        let mut trace = Vec::new();
+        let result = solve_equality(&data, "humn", Some(&mut trace));
+        trace.iter().for_each(|step| println!("{step}"));
+        match result {
+            Ok(human) => println!("Part 2: {human}"),
+            Err(e) => println!("Part 2: {e}"),
+        }
+    } else {
+        println!("Part 2: {}", part2(&data));
+    }
 }
 
 #[cfg(test)]
@@ -185,4 +121,20 @@ mod test {
         let (_, data) = parse(PUZZLE_INPUT).finish().expect("Parse error");
         assert_eq!(part2(&data), 3219579395609);
     }
+    #[test]
+    fn test_other_unknowns() {
+        let (_, data) = parse(TEST_INPUT).finish().expect("Parse error");
+        // sjmn = drzm * dbpl = 30 * dbpl must equal pppw = 2, which needs dbpl = 1/15
+        assert!(matches!(solve_equality(&data, "dbpl", None), Err(SolveError::NotInteger(_))));
+        // With humn at its original 5, sllz has to change to balance root
+        assert_eq!(solve_equality(&data, "sllz", None), Ok(596.into()));
+        let (_, data) = parse("root: aaaa + bbbb\naaaa: xxxx * xxxx\nbbbb: 4\nxxxx: 1").finish().unwrap();
+        assert_eq!(solve_equality(&data, "xxxx", None), Err(SolveError::Nonlinear(2)));
+        let (_, data) = parse("root: aaaa + bbbb\naaaa: xxxx + xxxx\nbbbb: 10\nxxxx: 1").finish().unwrap();
+        assert_eq!(solve_equality(&data, "xxxx", None), Ok(5.into()));
+        let (_, data) = parse("root: aaaa + bbbb\naaaa: cccc / xxxx\ncccc: xxxx * xxxx\nbbbb: 3\nxxxx: 1").finish().unwrap();
+        assert_eq!(solve_equality(&data, "xxxx", None), Ok(3.into()));
+        let (_, data) = parse("root: aaaa + bbbb\naaaa: xxxx / zero\nzero: 0\nbbbb: 3\nxxxx: 1").finish().unwrap();
+        assert_eq!(solve_equality(&data, "xxxx", None), Err(SolveError::DivisionByZero));
+    }
 }
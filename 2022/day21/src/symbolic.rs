@@ -0,0 +1,377 @@
+use num::{BigInt, BigRational, One, Zero};
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Const(BigRational),
+    Var(String),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn constant(value: i64) -> Self {
+        Expr::Const(BigRational::from_integer(value.into()))
+    }
+
+    // Folds constant subtrees and trivial identities
+    pub fn binary(op: Op, lhs: Expr, rhs: Expr) -> Result<Self, SolveError> {
+        use Expr::*;
+        Ok(match (op, lhs, rhs) {
+            (Op::Div, _, Const(b)) if b.is_zero() => return Err(SolveError::DivisionByZero),
+            (op, Const(a), Const(b)) => Const(apply(op, a, b)),
+            (Op::Add, Const(z), e) | (Op::Add | Op::Sub, e, Const(z)) if z.is_zero() => e,
+            (Op::Mul, Const(o), e) | (Op::Mul | Op::Div, e, Const(o)) if o.is_one() => e,
+            (op, lhs, rhs) => Binary(op, lhs.into(), rhs.into()),
+        })
+    }
+
+    pub fn as_const(&self) -> Option<&BigRational> {
+        match self {
+            Expr::Const(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    // Value with the variable set to `x`, None when it divides by zero on the way
+    fn eval(&self, x: &BigRational) -> Option<BigRational> {
+        match self {
+            Expr::Const(c) => Some(c.clone()),
+            Expr::Var(_) => Some(x.clone()),
+            Expr::Binary(op, lhs, rhs) => {
+                let (a, b) = (lhs.eval(x)?, rhs.eval(x)?);
+                (*op != Op::Div || !b.is_zero()).then(|| apply(*op, a, b))
+            }
+        }
+    }
+
+    fn to_rational_function(&self) -> Result<RationalFunction, SolveError> {
+        Ok(match self {
+            Expr::Const(c) => RationalFunction::constant(c.clone()),
+            Expr::Var(_) => RationalFunction {
+                num: Poly(vec![BigRational::zero(), BigRational::one()]),
+                den: Poly::one(),
+            },
+            Expr::Binary(op, lhs, rhs) => {
+                let (a, b) = (lhs.to_rational_function()?, rhs.to_rational_function()?);
+                if *op == Op::Div && b.num.degree().is_none() {
+                    return Err(SolveError::DivisionByZero);
+                }
+                match op {
+                    Op::Add => RationalFunction {
+                        num: a.num.mul(&b.den).add(&b.num.mul(&a.den)),
+                        den: a.den.mul(&b.den),
+                    },
+                    Op::Sub => RationalFunction {
+                        num: a.num.mul(&b.den).add(&b.num.mul(&a.den).neg()),
+                        den: a.den.mul(&b.den),
+                    },
+                    Op::Mul => RationalFunction {
+                        num: a.num.mul(&b.num),
+                        den: a.den.mul(&b.den),
+                    },
+                    Op::Div => RationalFunction {
+                        num: a.num.mul(&b.den),
+                        den: a.den.mul(&b.num),
+                    },
+                }
+                .normalized()
+            }
+        })
+    }
+}
+
+fn apply(op: Op, a: BigRational, b: BigRational) -> BigRational {
+    match op {
+        Op::Add => a + b,
+        Op::Sub => a - b,
+        Op::Mul => a * b,
+        Op::Div => a / b,
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Const(c) => write!(f, "{c}"),
+            Expr::Var(name) => write!(f, "{name}"),
+            Expr::Binary(op, lhs, rhs) => {
+                let op = match op {
+                    Op::Add => '+',
+                    Op::Sub => '-',
+                    Op::Mul => '*',
+                    Op::Div => '/',
+                };
+                write!(f, "({lhs} {op} {rhs})")
+            }
+        }
+    }
+}
+
+// Coefficients from the constant term up, without trailing zeros
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Poly(Vec<BigRational>);
+
+impl Poly {
+    fn one() -> Self {
+        Poly(vec![BigRational::one()])
+    }
+    fn trimmed(mut self) -> Self {
+        while self.0.last().is_some_and(|c| c.is_zero()) {
+            self.0.pop();
+        }
+        self
+    }
+    fn degree(&self) -> Option<usize> {
+        self.0.len().checked_sub(1)
+    }
+    fn add(&self, other: &Poly) -> Poly {
+        let len = self.0.len().max(other.0.len());
+        let zero = BigRational::zero();
+        Poly(
+            (0..len)
+                .map(|i| self.0.get(i).unwrap_or(&zero) + other.0.get(i).unwrap_or(&zero))
+                .collect(),
+        )
+        .trimmed()
+    }
+    fn neg(&self) -> Poly {
+        Poly(self.0.iter().map(|c| -c).collect())
+    }
+    fn mul(&self, other: &Poly) -> Poly {
+        if self.0.is_empty() || other.0.is_empty() {
+            return Poly(Vec::new());
+        }
+        let mut result = vec![BigRational::zero(); self.0.len() + other.0.len() - 1];
+        for (i, a) in self.0.iter().enumerate() {
+            for (j, b) in other.0.iter().enumerate() {
+                result[i + j] += a * b;
+            }
+        }
+        Poly(result).trimmed()
+    }
+    fn scaled(&self, factor: &BigRational) -> Poly {
+        Poly(self.0.iter().map(|c| c * factor).collect()).trimmed()
+    }
+    // Long division, `other` must not be zero
+    fn div_rem(&self, other: &Poly) -> (Poly, Poly) {
+        let lead = other.0.last().unwrap();
+        let mut quotient = Vec::new();
+        let mut rem = self.clone();
+        while rem.0.len() >= other.0.len() {
+            let shift = rem.0.len() - other.0.len();
+            let factor = rem.0.last().unwrap() / lead;
+            let mut term = vec![BigRational::zero(); shift + 1];
+            term[shift] = factor;
+            rem = rem.add(&other.mul(&Poly(term.clone())).neg());
+            quotient = Poly(quotient).add(&Poly(term)).0;
+        }
+        (Poly(quotient), rem)
+    }
+    // Monic greatest common divisor
+    fn gcd(&self, other: &Poly) -> Poly {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        while b.degree().is_some() {
+            let (_, rem) = a.div_rem(&b);
+            a = b;
+            b = rem;
+        }
+        match a.0.last() {
+            Some(lead) => a.scaled(&lead.recip()),
+            None => a,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RationalFunction {
+    num: Poly,
+    den: Poly,
+}
+
+impl RationalFunction {
+    fn constant(c: BigRational) -> Self {
+        RationalFunction {
+            num: Poly(vec![c]).trimmed(),
+            den: Poly::one(),
+        }
+    }
+    // Cancels common factors and keeps constant denominators folded into the numerator
+    fn normalized(self) -> Self {
+        let common = self.num.gcd(&self.den);
+        let (num, _) = self.num.div_rem(&common);
+        let (den, _) = self.den.div_rem(&common);
+        let reduced = RationalFunction { num, den };
+        match reduced.den.degree() {
+            Some(0) => {
+                let factor = reduced.den.0[0].recip();
+                RationalFunction {
+                    num: reduced.num.scaled(&factor),
+                    den: Poly::one(),
+                }
+            }
+            _ => reduced,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    // Both sides are the same for every value
+    Identity,
+    NoSolution,
+    Nonlinear(usize),
+    NotInteger(BigRational),
+    DivisionByZero,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::Identity => write!(f, "any value is a solution"),
+            SolveError::NoSolution => write!(f, "no solution"),
+            SolveError::Nonlinear(degree) => write!(f, "equation has degree {degree}"),
+            SolveError::NotInteger(x) => write!(f, "no integer solution, exact solution is {x}"),
+            SolveError::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+// Solves `lhs = rhs` for the single variable appearing in them
+pub fn solve(
+    lhs: &Expr,
+    rhs: &Expr,
+    mut trace: Option<&mut Vec<String>>,
+) -> Result<BigInt, SolveError> {
+    let (l, r) = (lhs.to_rational_function()?, rhs.to_rational_function()?);
+    // l.num / l.den = r.num / r.den  =>  l.num * r.den - r.num * l.den = 0
+    let p = l.num.mul(&r.den).add(&r.num.mul(&l.den).neg());
+    if let Some(trace) = trace.as_deref_mut() {
+        let terms: Vec<_> =
+            p.0.iter()
+                .enumerate()
+                .rev()
+                .map(|(i, c)| format!("{c}*x^{i}"))
+                .collect();
+        trace.push(format!("{} = 0", terms.join(" + ")));
+    }
+    let x = match p.degree() {
+        None => return Err(SolveError::Identity),
+        Some(0) => return Err(SolveError::NoSolution),
+        Some(1) => -&p.0[0] / &p.0[1],
+        Some(degree) => return Err(SolveError::Nonlinear(degree)),
+    };
+    if let Some(trace) = trace {
+        trace.push(format!("x = {x}"));
+    }
+    // Multiplying out or cancelling the denominators must not have introduced the solution
+    if lhs.eval(&x).is_none() || rhs.eval(&x).is_none() {
+        return Err(SolveError::NoSolution);
+    }
+    if x.is_integer() {
+        Ok(x.to_integer())
+    } else {
+        Err(SolveError::NotInteger(x))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn x() -> Expr {
+        Expr::Var("x".into())
+    }
+    fn c(v: i64) -> Expr {
+        Expr::constant(v)
+    }
+    fn solve_eq(lhs: Expr, rhs: Expr) -> Result<BigInt, SolveError> {
+        solve(&lhs, &rhs, None)
+    }
+    fn b(op: Op, lhs: Expr, rhs: Expr) -> Expr {
+        Expr::binary(op, lhs, rhs).unwrap()
+    }
+
+    #[test]
+    fn test_folding() {
+        let e = b(Op::Mul, b(Op::Add, c(1), c(2)), x());
+        assert_eq!(e.to_string(), "(3 * x)");
+        assert_eq!(b(Op::Div, b(Op::Add, x(), c(0)), c(1)), x());
+        assert_eq!(b(Op::Div, c(1), c(3)).to_string(), "1/3");
+    }
+    #[test]
+    fn test_linear() {
+        // (x + 3) * 2 = 10
+        let lhs = b(Op::Mul, b(Op::Add, x(), c(3)), c(2));
+        assert_eq!(solve_eq(lhs, c(10)), Ok(2.into()));
+        // x + x / 2 = 9, with x appearing twice
+        let lhs = b(Op::Add, x(), b(Op::Div, x(), c(2)));
+        assert_eq!(solve_eq(lhs, c(9)), Ok(6.into()));
+        // 12 / x = 4 is linear once multiplied out
+        assert_eq!(solve_eq(b(Op::Div, c(12), x()), c(4)), Ok(3.into()));
+    }
+    #[test]
+    fn test_failures() {
+        let half = b(Op::Mul, x(), c(2));
+        assert_eq!(
+            solve_eq(half, c(3)),
+            Err(SolveError::NotInteger(BigRational::new(3.into(), 2.into())))
+        );
+        let square = b(Op::Mul, x(), x());
+        assert_eq!(solve_eq(square, c(4)), Err(SolveError::Nonlinear(2)));
+        let same = b(Op::Sub, x(), x());
+        assert_eq!(solve_eq(same.clone(), c(0)), Err(SolveError::Identity));
+        assert_eq!(solve_eq(same, c(1)), Err(SolveError::NoSolution));
+        assert_eq!(
+            solve_eq(b(Op::Div, c(1), x()), c(0)),
+            Err(SolveError::NoSolution)
+        );
+    }
+    #[test]
+    fn test_trace() {
+        let mut trace = Vec::new();
+        let lhs = b(Op::Sub, c(10), x());
+        assert_eq!(solve(&lhs, &c(4), Some(&mut trace)), Ok(6.into()));
+        assert_eq!(trace, ["-1*x^1 + 6*x^0 = 0", "x = 6"]);
+    }
+    #[test]
+    fn test_cancelling() {
+        // x * x / x = 3 is linear once x cancels out
+        let lhs = b(Op::Div, b(Op::Mul, x(), x()), x());
+        assert_eq!(solve_eq(lhs.clone(), c(3)), Ok(3.into()));
+        // but x = 0 still divides by zero in the original
+        assert_eq!(solve_eq(lhs, c(0)), Err(SolveError::NoSolution));
+        // (x * x - 1) / (x + 1) = 4 cancels to x - 1 = 4
+        let num = b(Op::Sub, b(Op::Mul, x(), x()), c(1));
+        let lhs = b(Op::Div, num, b(Op::Add, x(), c(1)));
+        assert_eq!(solve_eq(lhs, c(4)), Ok(5.into()));
+        // x / x is 1 everywhere it is defined
+        assert_eq!(
+            solve_eq(b(Op::Div, x(), x()), c(1)),
+            Err(SolveError::Identity)
+        );
+    }
+    #[test]
+    fn test_division_by_zero() {
+        assert_eq!(
+            Expr::binary(Op::Div, x(), c(0)),
+            Err(SolveError::DivisionByZero)
+        );
+        assert_eq!(
+            Expr::binary(Op::Div, c(1), c(0)),
+            Err(SolveError::DivisionByZero)
+        );
+        let zero = b(Op::Sub, x(), x());
+        assert_eq!(
+            solve_eq(b(Op::Div, c(1), zero), c(1)),
+            Err(SolveError::DivisionByZero)
+        );
+    }
+}
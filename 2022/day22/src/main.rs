@@ -8,50 +8,9 @@ use nom::{
     Finish,
 };
 
+mod net;
+
 const PUZZLE_INPUT: &str = include_str!("../puzzle_input.txt");
-//   01
-//   2
-//  43
-//  5
-const PUZZLE_FACES: [(Position, [(usize, Direction); 4]); 6] = [
-    ((50, 0), [
-     (1, Direction::East),
-     (2, Direction::South),
-     (4, Direction::East),
-     (5, Direction::East),
-    ]),
-    ((100, 0), [
-     (3, Direction::West),
-     (2, Direction::West),
-     (0, Direction::West),
-     (5, Direction::North),
-    ]),
-    ((50, 50), [
-     (1, Direction::North),
-     (3, Direction::South),
-     (4, Direction::South),
-     (0, Direction::North),
-    ]),
-    ((50, 100), [
-     (1, Direction::West),
-     (5, Direction::West),
-     (4, Direction::West),
-     (2, Direction::North),
-    ]),
-    ((0, 100), [
-     (3, Direction::East),
-     (5, Direction::South),
-     (0, Direction::East),
-     (2, Direction::East),
-    ]),
-    ((0, 150), [
-     (3, Direction::North),
-     (1, Direction::South),
-     (0, Direction::South),
-     (4, Direction::North),
-    ]),
-    ];
-const PUZZLE_SIZE: usize = 50;
 
 
 type Coord = usize;
@@ -114,7 +73,7 @@ impl Character {
         }
     }
 }
-fn parse<'a>(input: &'a str) -> nom::IResult<&'a str, Data> {
+fn parse(input: &str) -> nom::IResult<&str, Data> {
     use Cell::*;
     let map_line = many1(alt((
         value(Wall, ncc::char('#')),
@@ -141,8 +100,7 @@ fn wrap_neighbor<'a>(map: &'a Map) -> impl Fn(Position, Direction) -> (Position,
         };
         let should_wrap = ((x == 0 && dx < 0) || (y == 0 && dy < 0)) 
             || map.get((y as i32 + dy) as usize)
-                .map(|row| row.get((x as i32 + dx) as usize))
-                .flatten()
+                .and_then(|row| row.get((x as i32 + dx) as usize))
                 .map(|c| c == &Cell::Empty)
                 .unwrap_or(true);
         if should_wrap {
@@ -219,7 +177,7 @@ fn cube_neighbor<'a>(map: &'a Map, faces: &'a [Face; 6], size: Coord) -> impl Fn
                 }
             }
         };
-        match map.get(dy).map(|row| row.get(dx)).flatten() {
+        match map.get(dy).and_then(|row| row.get(dx)) {
             Some(Cell::Floor) => ((dx, dy), dd),
             _ => ((x, y), d)
         }
@@ -257,10 +215,11 @@ fn part1((map, instructions): &Data) -> usize {
     (y + 1) * 1000 + (x + 1) * 4 + character.heading as Coord
 }
 
-fn part2((map, instructions): &Data, faces: &[Face; 6], size: Coord) -> usize {
+fn part2((map, instructions): &Data) -> usize {
+    let (size, faces) = net::fold(map).expect("Map is not a cube net");
     // Collect moves for visualization
     let mut moves = HashMap::new();
-    let neighbor = cube_neighbor(&map, faces, size);
+    let neighbor = cube_neighbor(map, &faces, size);
     let graph = graph_from_map(map, neighbor);
     let start_x = map[0].iter().enumerate().find_map(|(i, c)| (*c == Cell::Floor).then_some(i)).expect("No start position");
     let mut character = Character { position: (start_x, 0), heading: Direction::East };
@@ -306,7 +265,7 @@ fn part2((map, instructions): &Data, faces: &[Face; 6], size: Coord) -> usize {
 fn main() {
     let (_, data) = parse(PUZZLE_INPUT).finish().expect("Parse error");
     println!("Part 1: {}", part1(&data));
-    println!("Part 2: {}", part2(&data, &PUZZLE_FACES, PUZZLE_SIZE));
+    println!("Part 2: {}", part2(&data));
 }
 
 #[cfg(test)]
@@ -326,6 +285,12 @@ mod test {
     #[test]
     fn test_part2() {
         let (_, data) = parse(TEST_INPUT).finish().expect("Parse error");
+        assert_eq!(part2(&data), 5031);
+    }
+    #[test]
+    fn test_fold() {
+        // Formerly hand written faces for the test input, which had the 5 -> 0 heading wrong
+        let (_, (map, _)) = parse(TEST_INPUT).finish().expect("Parse error");
         let faces = [
             ((8, 0), [
              (5, Direction::West),
@@ -358,62 +323,22 @@ mod test {
              (3, Direction::North),
             ]),
             ((12, 8), [
-             (0, Direction::East),
+             (0, Direction::West),
              (1, Direction::East),
              (4, Direction::West),
              (3, Direction::West),
             ]),
         ];
-        let size = 4;
-        assert_eq!(part2(&data, &faces, size), 5031);
+        assert_eq!(net::fold(&map), Ok((4, faces)));
     }
     #[test]
     fn test_part2_custom() {
         let (_, data) = parse(include_str!("../custom_test_input.txt")).finish().expect("Parse error");
-        let faces = [
-            ((4, 0), [
-             (1, Direction::East),
-             (2, Direction::South),
-             (4, Direction::East),
-             (5, Direction::East),
-            ]),
-            ((8, 0), [
-             (3, Direction::West),
-             (2, Direction::West),
-             (0, Direction::West),
-             (5, Direction::North),
-            ]),
-            ((4, 4), [
-             (1, Direction::North),
-             (3, Direction::South),
-             (4, Direction::South),
-             (0, Direction::North),
-            ]),
-            ((4, 8), [
-             (1, Direction::West),
-             (5, Direction::West),
-             (4, Direction::West),
-             (2, Direction::North),
-            ]),
-            ((0, 8), [
-             (3, Direction::East),
-             (5, Direction::South),
-             (0, Direction::East),
-             (2, Direction::East),
-            ]),
-            ((0, 12), [
-             (3, Direction::North),
-             (1, Direction::South),
-             (0, Direction::South),
-             (4, Direction::North),
-            ]),
-            ];
-        let size = 4;
-        assert_eq!(part2(&data, &faces, size), 10008);
+        assert_eq!(part2(&data), 10008);
     }
     #[test]
     fn test_part2_puzzle() {
         let (_, data) = parse(PUZZLE_INPUT).finish().expect("Parse error");
-        assert_eq!(part2(&data, &PUZZLE_FACES, PUZZLE_SIZE), 182170);
+        assert_eq!(part2(&data), 182170);
     }
 }
@@ -0,0 +1,244 @@
+use crate::{Cell, Coord, Direction, Face, Map, Position};
+use std::collections::VecDeque;
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::East,
+    Direction::South,
+    Direction::West,
+    Direction::North,
+];
+
+type Vector = [i32; 3];
+
+fn neg(v: Vector) -> Vector {
+    v.map(|c| -c)
+}
+
+// A face folded into 3D: its outward normal and where east and south point on it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Orientation {
+    normal: Vector,
+    east: Vector,
+    south: Vector,
+}
+
+impl Orientation {
+    fn towards(&self, d: Direction) -> Vector {
+        match d {
+            Direction::East => self.east,
+            Direction::South => self.south,
+            Direction::West => neg(self.east),
+            Direction::North => neg(self.south),
+        }
+    }
+
+    // Orientation of the face next to this one in the net after folding the shared edge
+    fn fold(&self, d: Direction) -> Self {
+        let Orientation {
+            normal,
+            east,
+            south,
+        } = *self;
+        match d {
+            Direction::East => Orientation {
+                normal: east,
+                east: neg(normal),
+                south,
+            },
+            Direction::South => Orientation {
+                normal: south,
+                east,
+                south: neg(normal),
+            },
+            Direction::West => Orientation {
+                normal: neg(east),
+                east: normal,
+                south,
+            },
+            Direction::North => Orientation {
+                normal: neg(south),
+                east,
+                south: normal,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FoldError {
+    // Number of map cells can't be split into six square faces
+    Area(usize),
+    // Face-sized tile only partly covered by the map
+    PartialTile(Position),
+    Disconnected,
+    // Two tiles end up on the same side of the cube
+    Overlap(Position, Position),
+}
+
+// Finds the faces of a cube net and folds it to get the face size and face adjacency
+pub fn fold(map: &Map) -> Result<(Coord, [Face; 6]), FoldError> {
+    let area = map.iter().flatten().filter(|c| **c != Cell::Empty).count();
+    let size = (1..)
+        .take_while(|s| 6 * s * s <= area)
+        .last()
+        .filter(|s| 6 * s * s == area)
+        .ok_or(FoldError::Area(area))?;
+
+    let width = map.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut tiles: Vec<Position> = Vec::new();
+    for ty in (0..map.len()).step_by(size) {
+        for tx in (0..width).step_by(size) {
+            let covered = (ty..ty + size)
+                .flat_map(|y| (tx..tx + size).map(move |x| (x, y)))
+                .filter(|&(x, y)| {
+                    map.get(y)
+                        .and_then(|row| row.get(x))
+                        .is_some_and(|c| *c != Cell::Empty)
+                })
+                .count();
+            if covered == size * size {
+                tiles.push((tx, ty));
+            } else if covered > 0 {
+                return Err(FoldError::PartialTile((tx, ty)));
+            }
+        }
+    }
+
+    // Walk the net from the first tile, folding each edge crossed
+    let mut orientations = vec![None; tiles.len()];
+    orientations[0] = Some(Orientation {
+        normal: [0, 0, 1],
+        east: [1, 0, 0],
+        south: [0, 1, 0],
+    });
+    let mut queue = VecDeque::from([0]);
+    while let Some(i) = queue.pop_front() {
+        let (x, y) = tiles[i];
+        let orientation: Orientation = orientations[i].unwrap();
+        for d in DIRECTIONS {
+            let next = match d {
+                Direction::East => Some((x + size, y)),
+                Direction::South => Some((x, y + size)),
+                Direction::West => x.checked_sub(size).map(|x| (x, y)),
+                Direction::North => y.checked_sub(size).map(|y| (x, y)),
+            };
+            let Some(j) = next.and_then(|p| tiles.iter().position(|t| *t == p)) else {
+                continue;
+            };
+            if orientations[j].is_none() {
+                orientations[j] = Some(orientation.fold(d));
+                queue.push_back(j);
+            }
+        }
+    }
+    let orientations: Vec<Orientation> = orientations
+        .into_iter()
+        .collect::<Option<_>>()
+        .ok_or(FoldError::Disconnected)?;
+    for i in 0..tiles.len() {
+        for j in i + 1..tiles.len() {
+            if orientations[i].normal == orientations[j].normal {
+                return Err(FoldError::Overlap(tiles[i], tiles[j]));
+            }
+        }
+    }
+
+    // Six tiles with distinct normals cover every side, so each edge has a face beyond it
+    let faces: Vec<Face> = tiles
+        .iter()
+        .zip(&orientations)
+        .map(|(&tile, o)| {
+            let neighbors = DIRECTIONS.map(|d| {
+                let dest = orientations
+                    .iter()
+                    .position(|other| other.normal == o.towards(d))
+                    .unwrap();
+                // Crossing the edge turns the heading to point away from this face
+                let heading = DIRECTIONS
+                    .into_iter()
+                    .find(|dd| orientations[dest].towards(*dd) == neg(o.normal))
+                    .unwrap();
+                (dest, heading)
+            });
+            (tile, neighbors)
+        })
+        .collect();
+    Ok((size, faces.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cube_neighbor;
+
+    const NETS: [&str; 11] = [
+        "#\n####\n#",
+        "#\n####\n #",
+        "#\n####\n  #",
+        "#\n####\n   #",
+        " #\n####\n #",
+        " #\n####\n  #",
+        "##\n ###\n #",
+        "##\n ###\n  #",
+        "##\n ###\n   #",
+        "##\n ##\n  ##",
+        "###\n  ###",
+    ];
+
+    // Scales a net drawn with one character per face up to faces of `size` floor cells
+    fn scaled(net: &str, size: usize) -> Map {
+        net.lines()
+            .flat_map(|line| {
+                let row: Vec<Cell> = line
+                    .chars()
+                    .flat_map(|c| {
+                        let cell = if c == '#' { Cell::Floor } else { Cell::Empty };
+                        std::iter::repeat_n(cell, size)
+                    })
+                    .collect();
+                std::iter::repeat_n(row, size)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_all_nets() {
+        for net in NETS {
+            for size in 1..=4 {
+                let map = scaled(net, size);
+                let (found, faces) = fold(&map).unwrap();
+                assert_eq!(found, size);
+                let neighbor = cube_neighbor(&map, &faces, size);
+                // Going straight ahead on an empty cube leads around it and back
+                for (y, row) in map.iter().enumerate() {
+                    for (x, _) in row.iter().enumerate().filter(|(_, c)| **c == Cell::Floor) {
+                        for d in DIRECTIONS {
+                            let mut state = ((x, y), d);
+                            for _ in 0..4 * size {
+                                state = neighbor(state.0, state.1);
+                            }
+                            assert_eq!(state, ((x, y), d), "{net:?} size {size}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+    #[test]
+    fn test_invalid_nets() {
+        assert_eq!(
+            fold(&scaled("######", 2)),
+            Err(FoldError::Overlap((0, 0), (8, 0)))
+        );
+        assert_eq!(
+            fold(&scaled("###\n###", 1)),
+            Err(FoldError::Overlap((0, 1), (1, 1)))
+        );
+        assert_eq!(fold(&scaled("###\n\n###", 1)), Err(FoldError::Disconnected));
+        assert_eq!(fold(&scaled("#####", 3)), Err(FoldError::Area(45)));
+        let mut map = scaled("#\n####\n#", 2);
+        map[0].pop();
+        map[1].push(Cell::Floor);
+        assert_eq!(fold(&map), Err(FoldError::PartialTile((0, 0))));
+    }
+}
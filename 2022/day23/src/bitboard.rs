@@ -0,0 +1,313 @@
+use std::fmt;
+
+use crate::{Data, Position};
+
+const BITS: usize = u64::BITS as usize;
+// Rows and columns added on each side when elves get close to an edge
+const GROWTH: usize = BITS;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    South,
+    West,
+    East,
+}
+
+// Bit x of the result holds bit x - k of the row
+fn shifted_west(row: &[u64], i: usize, k: usize) -> u64 {
+    let carry = if i > 0 { row[i - 1] >> (BITS - k) } else { 0 };
+    (row[i] << k) | carry
+}
+
+// Bit x of the result holds bit x + k of the row
+fn shifted_east(row: &[u64], i: usize, k: usize) -> u64 {
+    let carry = if i + 1 < row.len() {
+        row[i + 1] << (BITS - k)
+    } else {
+        0
+    };
+    (row[i] >> k) | carry
+}
+
+// Elves as one bitset per row, bit x of a row is column x
+#[derive(Clone, Debug)]
+pub struct Board {
+    words: usize,
+    height: usize,
+    cells: Vec<u64>,
+    // Position of the first bit of the first row
+    origin: Position,
+    order: [Direction; 4],
+    round: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub round: usize,
+    pub top_left: Position,
+    pub rows: Vec<Vec<bool>>,
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.rows.iter().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for c in row {
+                write!(f, "{}", if *c { '#' } else { '.' })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Board {
+    pub fn new(data: &Data) -> Self {
+        let width = data.iter().map(|row| row.len()).max().unwrap_or(0);
+        let words = width.div_ceil(BITS).max(1);
+        let mut cells = vec![0; words * data.len()];
+        for (y, row) in data.iter().enumerate() {
+            for (x, _) in row.iter().enumerate().filter(|(_, elf)| **elf) {
+                cells[y * words + x / BITS] |= 1 << (x % BITS);
+            }
+        }
+        Board {
+            words,
+            height: data.len(),
+            cells,
+            origin: (0, 0),
+            order: [
+                Direction::North,
+                Direction::South,
+                Direction::West,
+                Direction::East,
+            ],
+            round: 0,
+        }
+    }
+
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.cells[y * self.words..(y + 1) * self.words]
+    }
+
+    pub fn elves(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..self.height).flat_map(move |y| {
+            self.row(y).iter().enumerate().flat_map(move |(i, &word)| {
+                let mut word = word;
+                std::iter::from_fn(move || {
+                    (word != 0).then(|| {
+                        let bit = word.trailing_zeros() as usize;
+                        word &= word - 1;
+                        let x = i * BITS + bit;
+                        (self.origin.0 + x as i32, self.origin.1 + y as i32)
+                    })
+                })
+            })
+        })
+    }
+
+    pub fn bounds(&self) -> Option<(Position, Position)> {
+        self.elves().fold(None, |acc, (x, y)| {
+            Some(match acc {
+                None => ((x, y), (x, y)),
+                Some(((x0, y0), (x1, y1))) => ((x0.min(x), y0.min(y)), (x1.max(x), y1.max(y))),
+            })
+        })
+    }
+
+    pub fn empty_ground(&self) -> usize {
+        match self.bounds() {
+            None => 0,
+            Some(((x0, y0), (x1, y1))) => ((x1 - x0 + 1) * (y1 - y0 + 1)) as usize - self.len(),
+        }
+    }
+
+    // Elves in the smallest rectangle containing all of them
+    pub fn snapshot(&self) -> Frame {
+        let Some(((x0, y0), (x1, y1))) = self.bounds() else {
+            return Frame {
+                round: self.round,
+                top_left: self.origin,
+                rows: Vec::new(),
+            };
+        };
+        let mut rows = vec![vec![false; (x1 - x0 + 1) as usize]; (y1 - y0 + 1) as usize];
+        for (x, y) in self.elves() {
+            rows[(y - y0) as usize][(x - x0) as usize] = true;
+        }
+        Frame {
+            round: self.round,
+            top_left: (x0, y0),
+            rows,
+        }
+    }
+
+    // Starting frame followed by a frame for each round until the elves stop moving
+    pub fn frames(mut self) -> impl Iterator<Item = Frame> {
+        let mut done = false;
+        std::iter::once(self.snapshot()).chain(std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            done = !self.step();
+            Some(self.snapshot())
+        }))
+    }
+
+    // Keeps the outermost rows and columns empty so moves never leave the board
+    fn reserve_margin(&mut self) {
+        let w = self.words;
+        let columns = self.cells.chunks(w).fold(0, |acc, row| {
+            acc | (row[0] & 1) | (row[w - 1] >> (BITS - 1))
+        });
+        let rows = self
+            .row(0)
+            .iter()
+            .chain(self.row(self.height - 1))
+            .any(|&word| word != 0);
+        if columns == 0 && !rows {
+            return;
+        }
+        let words = w + 2 * GROWTH / BITS;
+        let height = self.height + 2 * GROWTH;
+        let mut cells = vec![0; words * height];
+        for (y, row) in self.cells.chunks(w).enumerate() {
+            let at = (y + GROWTH) * words + GROWTH / BITS;
+            cells[at..at + w].copy_from_slice(row);
+        }
+        *self = Board {
+            words,
+            height,
+            cells,
+            origin: (self.origin.0 - GROWTH as i32, self.origin.1 - GROWTH as i32),
+            ..*self
+        };
+    }
+
+    // Plays one round, returns whether any elf moved
+    pub fn step(&mut self) -> bool {
+        if self.height == 0 {
+            self.round += 1;
+            return false;
+        }
+        self.reserve_margin();
+        let (w, h) = (self.words, self.height);
+
+        // Proposed moves indexed by direction, each as a plane of row bitsets
+        let mut proposals = [
+            vec![0u64; w * h],
+            vec![0u64; w * h],
+            vec![0u64; w * h],
+            vec![0u64; w * h],
+        ];
+        for y in 1..h - 1 {
+            let (n, c, s) = (self.row(y - 1), self.row(y), self.row(y + 1));
+            for i in 0..w {
+                let (nw, ne) = (shifted_west(n, i, 1), shifted_east(n, i, 1));
+                let (cw, ce) = (shifted_west(c, i, 1), shifted_east(c, i, 1));
+                let (sw, se) = (shifted_west(s, i, 1), shifted_east(s, i, 1));
+                let free = [
+                    !(nw | n[i] | ne),
+                    !(sw | s[i] | se),
+                    !(nw | cw | sw),
+                    !(ne | ce | se),
+                ];
+                let alone = free.iter().fold(!0, |acc, f| acc & f);
+                let mut remaining = c[i] & !alone;
+                for d in self.order {
+                    let proposed = remaining & free[d as usize];
+                    proposals[d as usize][y * w + i] = proposed;
+                    remaining &= !proposed;
+                }
+            }
+        }
+
+        let zero = vec![0u64; w];
+        let plane = |d: Direction, y: usize, dy: isize| -> &[u64] {
+            match y.checked_add_signed(dy).filter(|y| *y < h) {
+                Some(y) => &proposals[d as usize][y * w..(y + 1) * w],
+                None => &zero,
+            }
+        };
+        let mut cells = vec![0u64; w * h];
+        let mut moved = false;
+        for (y, row) in cells.chunks_mut(w).enumerate() {
+            let (north, south) = (plane(Direction::North, y, 0), plane(Direction::South, y, 0));
+            let (west, east) = (plane(Direction::West, y, 0), plane(Direction::East, y, 0));
+            let c = self.row(y);
+            for i in 0..w {
+                // Only elves coming from opposite sides can propose the same cell
+                let from_south = plane(Direction::North, y, 1)[i];
+                let from_north = plane(Direction::South, y, -1)[i];
+                let from_east = shifted_east(west, i, 1);
+                let from_west = shifted_west(east, i, 1);
+                let vertical = (from_south | from_north) & !(from_south & from_north);
+                let horizontal = (from_east | from_west) & !(from_east & from_west);
+                let blocked = (north[i] & plane(Direction::South, y, -2)[i])
+                    | (south[i] & plane(Direction::North, y, 2)[i])
+                    | (west[i] & shifted_west(east, i, 2))
+                    | (east[i] & shifted_east(west, i, 2));
+                let leaving = north[i] | south[i] | west[i] | east[i];
+                row[i] = (c[i] & !leaving) | blocked | vertical | horizontal;
+                moved |= vertical | horizontal != 0;
+            }
+        }
+        self.cells = cells;
+        self.order.rotate_left(1);
+        self.round += 1;
+        moved
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn board(picture: &str) -> Board {
+        let data: Data = picture
+            .lines()
+            .map(|line| line.chars().map(|c| c == '#').collect())
+            .collect();
+        Board::new(&data)
+    }
+
+    #[test]
+    fn test_small_example_frames() {
+        let frames: Vec<String> = board(".....\n..##.\n..#..\n.....\n..##.\n.....")
+            .frames()
+            .map(|frame| frame.to_string())
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                "##\n#.\n..\n##",
+                "##\n..\n#.\n.#\n#.",
+                ".##.\n#...\n...#\n....\n.#..",
+                "..#..\n....#\n#....\n....#\n.....\n..#..",
+                "..#..\n....#\n#....\n....#\n.....\n..#..",
+            ]
+        );
+    }
+    #[test]
+    fn test_growth() {
+        // Elves on the edge of a single word row spread across word boundaries
+        let mut b = board(&"#".repeat(BITS));
+        let before = b.len();
+        for _ in 0..200 {
+            b.step();
+        }
+        assert_eq!(b.len(), before);
+        let ((x0, _), (x1, _)) = b.bounds().unwrap();
+        assert!(x0 < 0 && x1 >= BITS as i32);
+    }
+}
@@ -9,13 +9,16 @@ use nom::{
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 
+mod bitboard;
+use bitboard::Board;
+
 const PUZZLE_INPUT: &str = include_str!("../puzzle_input.txt");
 type Data = Vec<Vec<bool>>;
 type Coord = i32;
 type Position = (Coord, Coord);
 type Elves = HashSet<Position>;
 type Order = [Position; 4];
-fn parse<'a>(input: &'a str) -> nom::IResult<&'a str, Data> {
+fn parse(input: &str) -> nom::IResult<&str, Data> {
     let line = many1(alt((
         value(false, ncc::char('.')),
         value(true, ncc::char('#')),
@@ -93,7 +96,8 @@ fn calculate_progress(elves: &Elves) -> usize {
         .filter(|pos| !elves.contains(pos))
         .count()
 }
-fn part1(data: &Data) -> usize {
+// The original HashSet version, kept for reference
+fn part1_reference(data: &Data) -> usize {
     let mut elves = elves_from_data(data);
     let mut order = [(0, -1), (0, 1), (-1, 0), (1, 0)];
     for _ in 0..10 {
@@ -102,7 +106,7 @@ fn part1(data: &Data) -> usize {
     calculate_progress(&elves)
 }
 
-fn part2(data: &Data) -> usize {
+fn part2_reference(data: &Data) -> usize {
     let mut elves = elves_from_data(data);
     let mut order = [(0, -1), (0, 1), (-1, 0), (1, 0)];
     (2..)
@@ -111,10 +115,39 @@ fn part2(data: &Data) -> usize {
         .unwrap_or(0)
 }
 
+fn part1(data: &Data) -> usize {
+    let mut board = Board::new(data);
+    for _ in 0..10 {
+        board.step();
+    }
+    board.empty_ground()
+}
+
+fn part2(data: &Data) -> usize {
+    let mut board = Board::new(data);
+    while board.step() {}
+    board.round()
+}
+
 fn main() {
     let (_, data) = parse(PUZZLE_INPUT).finish().expect("Parse error");
-    println!("Part 1: {}", part1(&data));
-    println!("Part 2: {}", part2(&data));
+    match std::env::args().nth(1).as_deref() {
+        Some("frames") => {
+            for frame in Board::new(&data).frames() {
+                let (x, y) = frame.top_left;
+                println!("== Round {} at ({x}, {y}) ==", frame.round);
+                println!("{frame}\n");
+            }
+        }
+        Some("reference") => {
+            println!("Part 1: {}", part1_reference(&data));
+            println!("Part 2: {}", part2_reference(&data));
+        }
+        _ => {
+            println!("Part 1: {}", part1(&data));
+            println!("Part 2: {}", part2(&data));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -141,4 +174,20 @@ mod test {
         let (_, data) = parse(PUZZLE_INPUT).finish().expect("Parse error");
         assert_eq!(part2(&data), 986);
     }
+    #[test]
+    fn test_matches_reference() {
+        let (_, data) = parse(TEST_INPUT).finish().expect("Parse error");
+        assert_eq!(part1(&data), part1_reference(&data));
+        assert_eq!(part2(&data), part2_reference(&data));
+        // Both simulations agree on every round of the puzzle input
+        let (_, data) = parse(PUZZLE_INPUT).finish().expect("Parse error");
+        let mut board = Board::new(&data);
+        let mut elves = elves_from_data(&data);
+        let mut order = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+        for _ in 0..100 {
+            board.step();
+            play_round(&mut elves, &mut order);
+            assert_eq!(board.elves().collect::<Elves>(), elves);
+        }
+    }
 }
@@ -0,0 +1,227 @@
+use std::collections::VecDeque;
+
+use crate::{Cell, Coord, Data, Direction, Position};
+
+// Storm bitmasks are single u128 words per row and column
+const MAX_SPAN: usize = u128::BITS as usize;
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RouteError {
+    // Waypoint is a wall or outside the valley
+    Blocked(Position),
+    // No way to reach the end of the leg with this index
+    Unreachable(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Route {
+    pub total: usize,
+    pub legs: Vec<usize>,
+}
+
+pub struct Valley {
+    // Size of the area inside the walls
+    width: Coord,
+    height: Coord,
+    pub entrance: Position,
+    pub exit: Position,
+    // Columns covered by sideways storms in each inner row, indexed by [t % width][y]
+    rows: Vec<Vec<u128>>,
+    // Rows covered by up and down storms in each inner column, indexed by [t % height][x]
+    columns: Vec<Vec<u128>>,
+    period: usize,
+}
+
+impl Valley {
+    pub fn new(data: &Data) -> Self {
+        let floor = |row: &[Cell]| row.iter().position(|c| *c == Cell::Floor).unwrap();
+        let entrance = (floor(data.first().unwrap()), 0);
+        let exit = (floor(data.last().unwrap()), data.len() - 1);
+        let width = data.iter().map(|row| row.len()).max().unwrap() - 2;
+        let height = data.len() - 2;
+        assert!(
+            width <= MAX_SPAN && height <= MAX_SPAN,
+            "Valley is too large ({width}x{height})"
+        );
+
+        let mut rows = vec![vec![0; height]; width];
+        let mut columns = vec![vec![0; width]; height];
+        let storms = data.iter().enumerate().flat_map(|(y, row)| {
+            row.iter().enumerate().filter_map(move |(x, c)| match c {
+                Cell::Storm(d) => Some((x - 1, y - 1, *d)),
+                _ => None,
+            })
+        });
+        for (x, y, direction) in storms {
+            match direction {
+                Direction::East | Direction::West => {
+                    for (t, masks) in rows.iter_mut().enumerate() {
+                        let x = match direction {
+                            Direction::East => (x + t) % width,
+                            _ => (x + width - t) % width,
+                        };
+                        masks[y] |= 1 << x;
+                    }
+                }
+                Direction::North | Direction::South => {
+                    for (t, masks) in columns.iter_mut().enumerate() {
+                        let y = match direction {
+                            Direction::South => (y + t) % height,
+                            _ => (y + height - t) % height,
+                        };
+                        masks[x] |= 1 << y;
+                    }
+                }
+            }
+        }
+
+        Valley {
+            width,
+            height,
+            entrance,
+            exit,
+            rows,
+            columns,
+            period: width / gcd(width, height) * height,
+        }
+    }
+
+    // Storms are back in their starting positions after this many minutes
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    fn is_inside(&self, (x, y): Position) -> bool {
+        (1..=self.width).contains(&x) && (1..=self.height).contains(&y)
+    }
+
+    fn is_walkable(&self, pos: Position) -> bool {
+        pos == self.entrance || pos == self.exit || self.is_inside(pos)
+    }
+
+    pub fn is_clear(&self, (x, y): Position, t: usize) -> bool {
+        if !self.is_inside((x, y)) {
+            return self.is_walkable((x, y));
+        }
+        let (ix, iy) = (x - 1, y - 1);
+        self.rows[t % self.width][iy] & (1 << ix) == 0
+            && self.columns[t % self.height][ix] & (1 << iy) == 0
+    }
+
+    // Earliest time to reach `to` when leaving `from` at time `start`
+    pub fn travel(&self, from: Position, to: Position, start: usize) -> Option<usize> {
+        let stride = self.width + 2;
+        let cells = stride * (self.height + 2);
+        let mut visited = vec![false; cells * self.period];
+        let index = |(x, y): Position, t: usize| (t % self.period) * cells + y * stride + x;
+        visited[index(from, start)] = true;
+        let mut queue = VecDeque::from([(from, start)]);
+        while let Some(((x, y), t)) = queue.pop_front() {
+            if (x, y) == to {
+                return Some(t);
+            }
+            let moves = [(0, 0), (1, 0), (0, 1), (-1, 0), (0, -1)];
+            for (dx, dy) in moves {
+                let (Some(nx), Some(ny)) = (x.checked_add_signed(dx), y.checked_add_signed(dy))
+                else {
+                    continue;
+                };
+                if self.is_walkable((nx, ny))
+                    && self.is_clear((nx, ny), t + 1)
+                    && !visited[index((nx, ny), t + 1)]
+                {
+                    visited[index((nx, ny), t + 1)] = true;
+                    queue.push_back(((nx, ny), t + 1));
+                }
+            }
+        }
+        None
+    }
+
+    // Visits the waypoints in order starting at time 0
+    pub fn route(&self, waypoints: &[Position]) -> Result<Route, RouteError> {
+        if let Some(&blocked) = waypoints.iter().find(|p| !self.is_walkable(**p)) {
+            return Err(RouteError::Blocked(blocked));
+        }
+        let mut t = 0;
+        let mut legs = Vec::new();
+        for (leg, pair) in waypoints.windows(2).enumerate() {
+            let arrival = self
+                .travel(pair[0], pair[1], t)
+                .ok_or(RouteError::Unreachable(leg))?;
+            legs.push(arrival - t);
+            t = arrival;
+        }
+        Ok(Route { total: t, legs })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use nom::Finish;
+
+    fn valley(input: &str) -> Valley {
+        let (_, data) = parse(input).finish().expect("Parse error");
+        Valley::new(&data)
+    }
+
+    #[test]
+    fn test_storm_masks() {
+        // Single storms moving right and down in a 5x5 area
+        let v = valley("#.#####\n#>....#\n#.....#\n#..v..#\n#.....#\n#.....#\n#####.#");
+        assert_eq!(v.period(), 5);
+        let storms = |t| {
+            (1..=5)
+                .flat_map(|y| (1..=5).map(move |x| (x, y)))
+                .filter(|p| !v.is_clear(*p, t))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(storms(0), [(1, 1), (3, 3)]);
+        assert_eq!(storms(3), [(3, 1), (4, 1)]);
+        assert_eq!(storms(5), storms(0));
+        assert!(v.is_clear(v.entrance, 3) && v.is_clear(v.exit, 3));
+        assert!(!v.is_clear((0, 1), 0));
+    }
+    #[test]
+    fn test_route_legs() {
+        let v = valley(include_str!("../test_input.txt"));
+        assert_eq!(v.period(), 12);
+        let route = v.route(&[v.entrance, v.exit, v.entrance, v.exit]);
+        assert_eq!(
+            route,
+            Ok(Route {
+                total: 54,
+                legs: vec![18, 23, 13]
+            })
+        );
+        // Waypoints inside the valley work just as well
+        let route = v.route(&[v.entrance, (6, 4), (1, 1)]).unwrap();
+        assert_eq!(route.legs.len(), 2);
+        assert_eq!(route.total, route.legs.iter().sum::<usize>());
+        assert_eq!(v.route(&[v.entrance]).map(|r| r.total), Ok(0));
+    }
+    #[test]
+    fn test_route_errors() {
+        let v = valley(include_str!("../test_input.txt"));
+        assert_eq!(
+            v.route(&[v.entrance, (0, 0)]),
+            Err(RouteError::Blocked((0, 0)))
+        );
+        // A row full of storms never opens up
+        let v = valley("#.###\n#>>>#\n#...#\n###.#");
+        assert_eq!(
+            v.route(&[v.entrance, v.exit]),
+            Err(RouteError::Unreachable(0))
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Sum;
+use std::marker::PhantomData;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+// Symbols for the digits of an odd base, from the most negative digit up
+pub trait Digits: Clone + fmt::Debug + Eq + std::hash::Hash {
+    const SYMBOLS: &'static [u8];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Quinary;
+impl Digits for Quinary {
+    const SYMBOLS: &'static [u8] = b"=-012";
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ternary;
+impl Digits for Ternary {
+    const SYMBOLS: &'static [u8] = b"-0+";
+}
+
+// Digits are least significant first with no leading zeros, so zero has no digits
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Balanced<D: Digits> {
+    digits: Vec<i8>,
+    base: PhantomData<D>,
+}
+
+pub type Snafu = Balanced<Quinary>;
+pub type BalancedTernary = Balanced<Ternary>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    InvalidDigit { position: usize, symbol: char },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty numeral"),
+            ParseError::InvalidDigit { position, symbol } => {
+                write!(f, "invalid digit {symbol:?} at position {position}")
+            }
+        }
+    }
+}
+
+impl<D: Digits> Balanced<D> {
+    // An even number of symbols has no digit for zero, so refuse it at compile time
+    const BASE: i64 = {
+        assert!(
+            D::SYMBOLS.len() % 2 == 1,
+            "Balanced digits need an odd base"
+        );
+        D::SYMBOLS.len() as i64
+    };
+    const HALF: i64 = Self::BASE / 2;
+
+    pub fn zero() -> Self {
+        Balanced {
+            digits: Vec::new(),
+            base: PhantomData,
+        }
+    }
+
+    // Carries any out of range digits into the next position
+    fn normalized(raw: Vec<i128>) -> Self {
+        let mut digits = Vec::with_capacity(raw.len() + 1);
+        let mut carry = 0;
+        let mut i = 0;
+        while i < raw.len() || carry != 0 {
+            let value = raw.get(i).copied().unwrap_or(0) + carry;
+            let (base, half) = (Self::BASE as i128, Self::HALF as i128);
+            let digit = (value + half).rem_euclid(base) - half;
+            carry = (value - digit) / base;
+            digits.push(digit as i8);
+            i += 1;
+        }
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Balanced {
+            digits,
+            base: PhantomData,
+        }
+    }
+
+    // Same value written with another set of digits
+    pub fn convert<E: Digits>(&self) -> Balanced<E> {
+        let base = Balanced::<E>::from(Self::BASE);
+        self.digits.iter().rev().fold(Balanced::zero(), |acc, d| {
+            acc * base.clone() + Balanced::from(*d as i64)
+        })
+    }
+
+    // The leading digit decides the sign
+    pub fn signum(&self) -> i8 {
+        self.digits.last().map_or(0, |d| d.signum())
+    }
+}
+
+impl<D: Digits> From<i64> for Balanced<D> {
+    fn from(value: i64) -> Self {
+        Self::normalized(vec![value as i128])
+    }
+}
+
+impl<D: Digits> TryFrom<&Balanced<D>> for i64 {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(value: &Balanced<D>) -> Result<Self, Self::Error> {
+        let value = value.digits.iter().rev().try_fold(0i128, |acc, d| {
+            acc.checked_mul(Balanced::<D>::BASE as i128)?
+                .checked_add(*d as i128)
+        });
+        // Anything beyond i128 is certainly beyond i64 too
+        i64::try_from(value.unwrap_or(i128::MAX))
+    }
+}
+
+impl<D: Digits> FromStr for Balanced<D> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        let raw = s
+            .chars()
+            .enumerate()
+            .map(|(position, symbol)| {
+                D::SYMBOLS
+                    .iter()
+                    .position(|c| *c as char == symbol)
+                    .map(|i| i as i128 - Self::HALF as i128)
+                    .ok_or(ParseError::InvalidDigit { position, symbol })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::normalized(raw.into_iter().rev().collect()))
+    }
+}
+
+impl<D: Digits> fmt::Display for Balanced<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.digits.is_empty() {
+            return write!(f, "{}", D::SYMBOLS[Self::HALF as usize] as char);
+        }
+        for d in self.digits.iter().rev() {
+            write!(
+                f,
+                "{}",
+                D::SYMBOLS[(*d as i64 + Self::HALF) as usize] as char
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<D: Digits> Neg for Balanced<D> {
+    type Output = Self;
+
+    fn neg(mut self) -> Self {
+        self.digits.iter_mut().for_each(|d| *d = -*d);
+        self
+    }
+}
+
+impl<D: Digits> Add for Balanced<D> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let len = self.digits.len().max(rhs.digits.len());
+        let digit = |n: &Self, i| n.digits.get(i).copied().unwrap_or(0) as i128;
+        Self::normalized((0..len).map(|i| digit(&self, i) + digit(&rhs, i)).collect())
+    }
+}
+
+impl<D: Digits> Sub for Balanced<D> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl<D: Digits> Mul for Balanced<D> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        if self.digits.is_empty() || rhs.digits.is_empty() {
+            return Self::zero();
+        }
+        let mut raw = vec![0; self.digits.len() + rhs.digits.len()];
+        for (i, a) in self.digits.iter().enumerate() {
+            for (j, b) in rhs.digits.iter().enumerate() {
+                raw[i + j] += (*a as i128) * (*b as i128);
+            }
+        }
+        Self::normalized(raw)
+    }
+}
+
+impl<D: Digits> Sum for Balanced<D> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, x| acc + x)
+    }
+}
+
+impl<D: Digits> Ord for Balanced<D> {
+    // Normalized digits never outweigh the ones above them, so more digits means a larger
+    // magnitude and equal lengths compare from the leading digit down
+    fn cmp(&self, other: &Self) -> Ordering {
+        let sign = self.signum();
+        sign.cmp(&other.signum()).then_with(|| {
+            let longer = self.digits.len().cmp(&other.digits.len());
+            let longer = if sign < 0 { longer.reverse() } else { longer };
+            longer.then_with(|| self.digits.iter().rev().cmp(other.digits.iter().rev()))
+        })
+    }
+}
+
+impl<D: Digits> PartialOrd for Balanced<D> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn snafu(s: &str) -> Snafu {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for value in -1000..=1000 {
+            let s = Snafu::from(value);
+            assert_eq!(i64::try_from(&s), Ok(value));
+            assert_eq!(snafu(&s.to_string()), s);
+            let t = BalancedTernary::from(value);
+            assert_eq!(t.to_string().parse::<BalancedTernary>(), Ok(t.clone()));
+            assert_eq!(i64::try_from(&t), Ok(value));
+        }
+        assert_eq!(BalancedTernary::from(8).to_string(), "+0-");
+        assert_eq!(Snafu::from(0).to_string(), "0");
+        assert_eq!(snafu("0012"), Snafu::from(7));
+        for value in [i64::MIN, i64::MAX] {
+            assert_eq!(i64::try_from(&Snafu::from(value)), Ok(value));
+        }
+    }
+    #[test]
+    fn test_arithmetic() {
+        let values = [-130, -26, -7, -1, 0, 1, 3, 12, 44, 625, 9999];
+        for a in values {
+            for b in values {
+                let (x, y) = (Snafu::from(a), Snafu::from(b));
+                assert_eq!(x.clone() + y.clone(), Snafu::from(a + b));
+                assert_eq!(x.clone() - y.clone(), Snafu::from(a - b));
+                assert_eq!(x.clone() * y.clone(), Snafu::from(a * b));
+                assert_eq!(x.cmp(&y), a.cmp(&b));
+                let (x, y) = (BalancedTernary::from(a), BalancedTernary::from(b));
+                assert_eq!(x.clone() * -y.clone(), BalancedTernary::from(-a * b));
+                assert_eq!(x.cmp(&y), a.cmp(&b));
+            }
+        }
+    }
+    #[test]
+    fn test_beyond_i64() {
+        let max = Snafu::from(i64::MAX);
+        let twice = max.clone() + max.clone();
+        assert!(i64::try_from(&twice).is_err());
+        assert!(twice > max);
+        assert!(-twice.clone() < -max.clone());
+        assert!(-max.clone() < Snafu::zero());
+        assert_eq!(twice.clone() - max.clone(), max);
+        let square = max.clone() * max.clone();
+        assert_eq!(square.clone() - square, Snafu::zero());
+        // 5^40 written directly and as a product
+        let big = snafu(&format!("1{}", "0".repeat(40)));
+        assert_eq!(big.to_string().len(), 41);
+        let five = snafu("10");
+        let power = (0..40).fold(snafu("1"), |acc, _| acc * five.clone());
+        assert_eq!(power, big);
+        assert_eq!(big.convert::<Ternary>().convert::<Quinary>(), big);
+    }
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!("".parse::<Snafu>(), Err(ParseError::Empty));
+        assert_eq!(
+            "1=3".parse::<Snafu>(),
+            Err(ParseError::InvalidDigit {
+                position: 2,
+                symbol: '3'
+            })
+        );
+        assert!("1=-".parse::<BalancedTernary>().is_err());
+    }
+}
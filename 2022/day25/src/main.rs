@@ -1,97 +1,37 @@
 use nom::{
-    branch::alt,
+    bytes::complete::is_a,
     character::complete as ncc,
-    combinator::{all_consuming, map, value},
-    multi::{many1, separated_list1},
+    combinator::{all_consuming, map_res},
+    multi::separated_list1,
     sequence::delimited,
     Finish,
 };
 
+mod balanced;
+use balanced::{BalancedTernary, Snafu};
+
 const PUZZLE_INPUT: &str = include_str!("../puzzle_input.txt");
 
-struct Snafu(Vec<i64>);
 type Data = Vec<Snafu>;
 
-fn parse<'a>(input: &'a str) -> nom::IResult<&'a str, Data> {
-    let line = map(
-        many1(alt((
-            value(-2, ncc::char('=')),
-            value(-1, ncc::char('-')),
-            value(0, ncc::char('0')),
-            value(1, ncc::char('1')),
-            value(2, ncc::char('2')),
-        ))),
-        Snafu,
-    );
+fn parse(input: &str) -> nom::IResult<&str, Data> {
+    let line = map_res(is_a("=-012"), str::parse);
     let data = separated_list1(ncc::line_ending, line);
     all_consuming(delimited(ncc::multispace0, data, ncc::multispace0))(input)
 }
 
-impl From<&Snafu> for i64 {
-    fn from(value: &Snafu) -> Self {
-        value
-            .0
-            .iter()
-            .rev()
-            .enumerate()
-            .map(|(i, x)| x * 5i64.pow(i as u32))
-            .sum::<i64>()
-    }
-}
-
-impl From<i64> for Snafu {
-    fn from(value: i64) -> Self {
-        let digits = 2 + (value as f64).log(5.0).ceil() as u32;
-        let snafu = (0..digits)
-            .map(|i| {
-                let m = 5i64.pow(i);
-                value % (5 * m) / m
-            })
-            .scan(0, |carry, value| {
-                let digit = *carry + value;
-                if digit > 2 {
-                    *carry = 1;
-                    Some(digit - 5)
-                } else {
-                    *carry = 0;
-                    Some(digit)
-                }
-            })
-            .collect::<Vec<_>>()
-            .into_iter()
-            .rev();
-        Self(snafu.skip_while(|v| *v == 0).collect())
-    }
-}
-
-impl ToString for Snafu {
-    fn to_string(&self) -> String {
-        self.0
-            .iter()
-            .map(|x| match x {
-                -2 => '=',
-                -1 => '-',
-                0 => '0',
-                1 => '1',
-                2 => '2',
-                _ => unreachable!(),
-            })
-            .collect()
-    }
-}
 fn part1(data: &Data) -> String {
-    let sum = data.iter().map(i64::from).sum::<i64>();
-    Snafu::from(sum).to_string()
-}
-
-fn part2(data: &Data) -> usize {
-    todo!()
+    data.iter().cloned().sum::<Snafu>().to_string()
 }
 
 fn main() {
     let (_, data) = parse(PUZZLE_INPUT).finish().expect("Parse error");
     println!("Part 1: {}", part1(&data));
-    println!("Part 2: {}", part2(&data));
+    if std::env::args().nth(1).as_deref() == Some("ternary") {
+        // Same sum written in balanced ternary
+        let sum: BalancedTernary = data.iter().cloned().sum::<Snafu>().convert();
+        println!("Balanced ternary: {sum}");
+    }
 }
 
 #[cfg(test)]
@@ -131,16 +71,4 @@ mod test {
         let (_, data) = parse(PUZZLE_INPUT).finish().expect("Parse error");
         assert_eq!(part1(&data), "2-1=10=1=1==2-1=-221");
     }
-    /*
-    #[test]
-    fn test_part2() {
-        let (_, data) = parse(TEST_INPUT).finish().expect("Parse error");
-        assert_eq!(part2(&data), 20);
-    }
-    #[test]
-    fn test_part2_puzzle() {
-        let (_, data) = parse(PUZZLE_INPUT).finish().expect("Parse error");
-        assert_eq!(part2(&data), 986);
-    }
-    */
 }